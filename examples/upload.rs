@@ -2,7 +2,7 @@ use std::io::Error as IoError;
 use std::path::Path;
 use std::sync::Arc;
 
-use async_std::{fs::OpenOptions, io};
+use async_std::{fs::OpenOptions, io, prelude::*};
 use tempfile::TempDir;
 use tide::prelude::*;
 use tide::{Body, Request, Response, StatusCode};
@@ -34,6 +34,35 @@ async fn main() -> Result<(), IoError> {
     // $ cargo run --example upload
     // $ curl -T ./README.md localhost:8080 # this writes the file to a temp directory
     // $ curl localhost:8080/README.md # this reads the file from the same temp directory
+    // $ curl -F file=@./README.md localhost:8080 # this uploads the file as a form
+
+    app.at("/")
+        .post(|mut req: Request<TempDirState>| async move {
+            let dir = req.state().path().to_owned();
+            let mut parts = req.body_multipart()?.field_limit(10 * 1024 * 1024);
+            let mut uploaded = Vec::new();
+
+            while let Some(part) = parts.next().await {
+                let mut part = part?;
+                let name = match part.filename().and_then(|name| Path::new(name).file_name()) {
+                    Some(name) => name.to_owned(),
+                    None => continue,
+                };
+
+                let file = OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .open(dir.join(&name))
+                    .await?;
+                // A failed part is followed by its error, such as a 413 for
+                // files over the limit.
+                if io::copy(&mut part, file).await.is_ok() {
+                    uploaded.push(name.to_string_lossy().into_owned());
+                }
+            }
+
+            Ok(json!({ "uploaded": uploaded }))
+        });
 
     app.at(":file")
         .put(|req: Request<TempDirState>| async move {
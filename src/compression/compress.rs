@@ -0,0 +1,222 @@
+use async_compression::futures::bufread::{BrotliEncoder, GzipEncoder, ZlibEncoder};
+use async_compression::Level;
+use async_std::io::BufReader;
+use http_types::content::{AcceptEncoding, ContentEncoding, Encoding};
+use http_types::headers;
+use http_types::{Body, Method, Mime, StatusCode};
+
+use crate::middleware::{Middleware, Next};
+use crate::{Request, Response, Result};
+
+/// Bodies smaller than this are not worth compressing.
+const DEFAULT_MIN_SIZE: usize = 1024;
+
+/// Middleware compressing response bodies with the best encoding accepted by
+/// the client.
+///
+/// The encoding is negotiated from the `Accept-Encoding` request header, out
+/// of `br`, `gzip` and `deflate` by default. Bodies are compressed as they
+/// are sent, so the `Content-Length` header is dropped from compressed
+/// responses.
+///
+/// Responses are left as they are if they:
+///
+/// - already have a `Content-Encoding`,
+/// - are known to be smaller than the minimum size,
+/// - have a content type which does not compress well, such as images or
+///   `text/event-stream`,
+/// - are `206 Partial Content` responses,
+/// - or have a `Cache-Control: no-transform` header.
+///
+/// All other responses get a `Vary: Accept-Encoding` header, whether they are
+/// compressed or not.
+///
+/// # Examples
+///
+/// ```
+/// use tide::compression::{CompressMiddleware, Encoding};
+///
+/// let mut app = tide::new();
+/// app.with(
+///     CompressMiddleware::new()
+///         .encodings(vec![Encoding::Gzip])
+///         .min_size(256),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompressMiddleware {
+    encodings: Vec<Encoding>,
+    min_size: usize,
+}
+
+impl CompressMiddleware {
+    /// Create a new instance of `CompressMiddleware`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            encodings: vec![Encoding::Brotli, Encoding::Gzip, Encoding::Deflate],
+            min_size: DEFAULT_MIN_SIZE,
+        }
+    }
+
+    /// Set the encodings to use, in order of preference.
+    ///
+    /// The server's preference only breaks ties between the encodings the
+    /// client accepts with the same weight.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an encoding other than `Brotli`, `Gzip` or `Deflate` is
+    /// given.
+    #[must_use]
+    pub fn encodings(mut self, encodings: impl IntoIterator<Item = Encoding>) -> Self {
+        self.encodings = encodings.into_iter().collect();
+        for encoding in &self.encodings {
+            assert!(
+                matches!(
+                    encoding,
+                    Encoding::Brotli | Encoding::Gzip | Encoding::Deflate
+                ),
+                "Unsupported encoding `{}`",
+                encoding
+            );
+        }
+        self
+    }
+
+    /// Set the size in bytes below which bodies are not compressed. Defaults
+    /// to 1024.
+    ///
+    /// Bodies of unknown length are always compressed.
+    #[must_use]
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Pick the encoding with the highest weight in `Accept-Encoding`, if any.
+    fn negotiate<State>(&self, req: &Request<State>) -> Option<Encoding> {
+        let accept = AcceptEncoding::from_headers(req).ok()??;
+
+        let mut best: Option<(f32, Encoding)> = None;
+        for encoding in &self.encodings {
+            let weight = accept
+                .iter()
+                .find(|proposal| proposal.encoding() == encoding)
+                .map(|proposal| proposal.weight().unwrap_or(1.0))
+                .or_else(|| accept.wildcard().then_some(1.0));
+            match (weight, best) {
+                (Some(weight), Some((best_weight, _))) if weight <= best_weight => {}
+                (Some(weight), _) if weight > 0.0 => best = Some((weight, *encoding)),
+                _ => {}
+            }
+        }
+        best.map(|(_, encoding)| encoding)
+    }
+
+    /// Whether the response may be compressed, whatever the client accepts.
+    fn is_eligible(&self, res: &Response) -> bool {
+        if res.header(headers::CONTENT_ENCODING).is_some()
+            || res.status() == StatusCode::PartialContent
+            || res.len().is_some_and(|len| len < self.min_size)
+        {
+            return false;
+        }
+
+        let no_transform = res.header(headers::CACHE_CONTROL).is_some_and(|values| {
+            values.iter().any(|value| {
+                value
+                    .as_str()
+                    .split(',')
+                    .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"))
+            })
+        });
+        !no_transform
+            && res
+                .content_type()
+                .is_some_and(|mime| is_compressible(&mime))
+    }
+}
+
+#[async_trait::async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for CompressMiddleware {
+    async fn handle(&self, req: Request<State>, next: Next<'_, State>) -> Result {
+        let encoding = match req.method() {
+            Method::Head => None,
+            _ => self.negotiate(&req),
+        };
+
+        let mut res = next.run(req).await;
+        if !self.is_eligible(&res) {
+            return Ok(res);
+        }
+
+        let vary = res.header(headers::VARY).is_some_and(|values| {
+            values.iter().any(|value| {
+                value.as_str().split(',').any(|name| {
+                    let name = name.trim();
+                    name == "*" || name.eq_ignore_ascii_case("accept-encoding")
+                })
+            })
+        });
+        if !vary {
+            res.append_header(headers::VARY, "Accept-Encoding");
+        }
+
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => return Ok(res),
+        };
+
+        let body = res.take_body();
+        let mime = body.mime().clone();
+        let mut body = match encoding {
+            Encoding::Brotli => {
+                // The default brotli quality is too slow for compressing on
+                // the fly.
+                let encoder = BrotliEncoder::with_quality(body, Level::Precise(4));
+                Body::from_reader(BufReader::new(encoder), None)
+            }
+            Encoding::Gzip => Body::from_reader(BufReader::new(GzipEncoder::new(body)), None),
+            // HTTP's `deflate` is the zlib format, not raw DEFLATE.
+            _ => Body::from_reader(BufReader::new(ZlibEncoder::new(body)), None),
+        };
+        body.set_mime(mime);
+        res.set_body(body);
+        res.remove_header(headers::CONTENT_LENGTH);
+        ContentEncoding::new(encoding).apply(&mut res);
+
+        // The compressed body is no longer byte for byte the same.
+        if let Some(etag) = res
+            .header(headers::ETAG)
+            .map(|etag| etag.as_str().to_owned())
+        {
+            if !etag.starts_with("W/") {
+                res.insert_header(headers::ETAG, format!("W/{}", etag));
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl Default for CompressMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether bodies of this type are worth compressing.
+fn is_compressible(mime: &Mime) -> bool {
+    match (mime.basetype(), mime.subtype()) {
+        ("text", "event-stream") => false,
+        ("text", _) => true,
+        ("image", "svg+xml") => true,
+        ("application", subtype) => {
+            matches!(subtype, "json" | "javascript" | "xml" | "wasm")
+                || subtype.ends_with("+json")
+                || subtype.ends_with("+xml")
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,133 @@
+use async_compression::futures::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder};
+use async_std::io::{BufReader, Read};
+use http_types::{headers, Body, StatusCode};
+
+use crate::middleware::{Middleware, Next};
+use crate::request::{BodyLimit, Limited};
+use crate::{Request, Response, Result};
+
+/// The default limit on the size of decompressed bodies.
+const DEFAULT_MAX_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Middleware decompressing request bodies sent with a `Content-Encoding`.
+///
+/// Bodies encoded with `gzip`, `deflate` or `br`, or with several of them,
+/// are decompressed as they are read, and the `Content-Encoding` and
+/// `Content-Length` headers are removed from the request. Requests with any
+/// other content coding are answered with `415 Unsupported Media Type`.
+///
+/// Decompressed bodies are limited in size, so a small compressed body
+/// cannot expand without bound. Reading past the limit through
+/// [`Request::body_bytes`] and the other body methods fails with `413 Payload
+/// Too Large`, while reading the `Request` directly fails with an IO error.
+///
+/// # Examples
+///
+/// ```
+/// use tide::compression::DecompressMiddleware;
+///
+/// let mut app = tide::new();
+/// app.with(DecompressMiddleware::new().max_size(1024 * 1024));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DecompressMiddleware {
+    max_size: u64,
+}
+
+impl DecompressMiddleware {
+    /// Create a new instance of `DecompressMiddleware`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Set the maximum size in bytes of decompressed bodies. Defaults to 8
+    /// MiB.
+    ///
+    /// A lower body limit set with
+    /// [`Server::body_limit`](crate::Server::body_limit) still applies.
+    #[must_use]
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+}
+
+/// A content coding the middleware can undo.
+#[derive(Debug, Clone, Copy)]
+enum Coding {
+    Brotli,
+    Deflate,
+    Gzip,
+}
+
+#[async_trait::async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for DecompressMiddleware {
+    async fn handle(&self, mut req: Request<State>, next: Next<'_, State>) -> Result {
+        let mut codings = Vec::new();
+        if let Some(values) = req.header(headers::CONTENT_ENCODING) {
+            let names = values.iter().flat_map(|value| value.as_str().split(','));
+            for name in names.map(str::trim).filter(|name| !name.is_empty()) {
+                let coding = match name.to_ascii_lowercase().as_str() {
+                    "identity" => continue,
+                    "br" => Coding::Brotli,
+                    "deflate" => Coding::Deflate,
+                    "gzip" | "x-gzip" => Coding::Gzip,
+                    _ => {
+                        let mut res = Response::new(StatusCode::UnsupportedMediaType);
+                        res.insert_header(headers::ACCEPT_ENCODING, "br, deflate, gzip");
+                        return Ok(res);
+                    }
+                };
+                codings.push(coding);
+            }
+            req.remove_header(headers::CONTENT_ENCODING);
+        }
+
+        if codings.is_empty() {
+            return Ok(next.run(req).await);
+        }
+
+        // Codings are listed in the order they were applied.
+        let mut body = req.take_body();
+        let mime = body.mime().clone();
+        for coding in codings.into_iter().rev() {
+            body = match coding {
+                Coding::Brotli => decoded(BrotliDecoder::new(body)),
+                Coding::Deflate => decoded(ZlibDecoder::new(body)),
+                Coding::Gzip => decoded(GzipDecoder::new(body)),
+            };
+        }
+        let mut body = decoded(Limited::new(body, self.max_size));
+        body.set_mime(mime);
+
+        // Keep the request without a content type if it had none.
+        let had_content_type = req.header(headers::CONTENT_TYPE).is_some();
+        req.set_body(body);
+        if !had_content_type {
+            req.remove_header(headers::CONTENT_TYPE);
+        }
+        req.remove_header(headers::CONTENT_LENGTH);
+
+        let limit = match req.ext::<BodyLimit>() {
+            Some(BodyLimit(limit)) => (*limit).min(self.max_size),
+            None => self.max_size,
+        };
+        req.set_ext(BodyLimit(limit));
+
+        Ok(next.run(req).await)
+    }
+}
+
+impl Default for DecompressMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A body of unknown length read from `reader`.
+fn decoded(reader: impl Read + Unpin + Send + Sync + 'static) -> Body {
+    Body::from_reader(BufReader::new(reader), None)
+}
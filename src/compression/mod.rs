@@ -0,0 +1,26 @@
+//! HTTP body compression.
+//!
+//! [`CompressMiddleware`] compresses response bodies, and
+//! [`DecompressMiddleware`] decompresses request bodies.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
+//! #
+//! use tide::compression::{CompressMiddleware, DecompressMiddleware};
+//!
+//! let mut app = tide::new();
+//! app.with(CompressMiddleware::new());
+//! app.with(DecompressMiddleware::new());
+//! app.at("/").get(|_| async { Ok("Hello, world!".repeat(100)) });
+//! app.listen("localhost:8080").await?;
+//! # Ok(()) }) }
+//! ```
+
+mod compress;
+mod decompress;
+
+pub use compress::CompressMiddleware;
+pub use decompress::DecompressMiddleware;
+pub use http_types::content::Encoding;
@@ -0,0 +1,174 @@
+//! Conditional GET requests.
+//!
+//! [`ConditionalMiddleware`] answers `GET` and `HEAD` requests whose
+//! `If-None-Match` or `If-Modified-Since` validators match the response with
+//! `304 Not Modified`, so clients need not download an unchanged body again.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
+//! #
+//! use tide::conditional::ConditionalMiddleware;
+//!
+//! let mut app = tide::new();
+//! app.with(ConditionalMiddleware::new());
+//! app.at("/").get(|_| async { Ok("Hello, world!") });
+//! app.at("/public/*").serve_dir("public/")?;
+//! app.listen("localhost:8080").await?;
+//! # Ok(()) }) }
+//! ```
+
+use crate::http::conditional::{ETag, IfModifiedSince, IfNoneMatch, LastModified};
+use crate::http::{headers, Body, Method, StatusCode};
+use crate::utils::Fnv1a;
+use crate::{Middleware, Next, Request, Response, Result};
+
+/// The largest body an `ETag` is computed for by default.
+const DEFAULT_MAX_SIZE: usize = 1024 * 1024;
+
+/// Headers a `304 Not Modified` response keeps from the full response.
+const KEPT_HEADERS: [&str; 7] = [
+    "cache-control",
+    "content-location",
+    "date",
+    "etag",
+    "expires",
+    "last-modified",
+    "vary",
+];
+
+/// Middleware answering conditional `GET` and `HEAD` requests.
+///
+/// Successful responses, including partial ones, are compared with the
+/// request's validators:
+///
+/// - If the request has an `If-None-Match` header, the response is not
+///   modified if one of the given ETags matches its `ETag`.
+/// - Otherwise, if the request has an `If-Modified-Since` header, the
+///   response is not modified if its `Last-Modified` date is not later.
+///
+/// Unmodified responses are replaced with an empty `304 Not Modified`
+/// response.
+///
+/// Endpoints provide validators by setting the `ETag` and `Last-Modified`
+/// headers, as [`Route::serve_dir`](crate::Route::serve_dir) and
+/// [`Route::serve_file`](crate::Route::serve_file) do. Responses without an
+/// `ETag` whose body has a known length of at most 1 MiB get a strong `ETag`
+/// computed from their body.
+///
+/// # Examples
+///
+/// ```
+/// use tide::conditional::ConditionalMiddleware;
+/// use tide::http::conditional::ETag;
+/// use tide::Response;
+///
+/// let mut app = tide::new();
+/// app.with(ConditionalMiddleware::new().max_size(64 * 1024));
+/// app.at("/version").get(|_| async {
+///     let mut res = Response::new(200);
+///     ETag::new("v1".into()).apply(&mut res);
+///     res.set_body("v1");
+///     Ok(res)
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConditionalMiddleware {
+    max_size: usize,
+}
+
+impl ConditionalMiddleware {
+    /// Create a new instance of `ConditionalMiddleware`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Set the size in bytes of the largest body an `ETag` is computed for.
+    /// Defaults to 1 MiB; `0` disables computing ETags.
+    #[must_use]
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for ConditionalMiddleware {
+    async fn handle(&self, req: Request<State>, next: Next<'_, State>) -> Result {
+        if !matches!(req.method(), Method::Get | Method::Head) {
+            return Ok(next.run(req).await);
+        }
+
+        // Malformed validators are ignored, as if they had not been sent.
+        let if_none_match = IfNoneMatch::from_headers(&req).ok().flatten();
+        let if_modified_since = IfModifiedSince::from_headers(&req).ok().flatten();
+
+        let mut res = next.run(req).await;
+        if !matches!(res.status(), StatusCode::Ok | StatusCode::PartialContent) {
+            return Ok(res);
+        }
+
+        if res.status() == StatusCode::Ok
+            && res.header(headers::ETAG).is_none()
+            && res.len().is_some_and(|len| len > 0 && len <= self.max_size)
+        {
+            let body = res.take_body();
+            let mime = body.mime().clone();
+            let bytes = body.into_bytes().await?;
+            ETag::new(hash(&bytes)).apply(&mut res);
+            let mut body = Body::from_bytes(bytes);
+            body.set_mime(mime);
+            res.set_body(body);
+        }
+
+        let not_modified = match if_none_match {
+            Some(if_none_match) => {
+                let etag = ETag::from_headers(&res).ok().flatten();
+                if_none_match.wildcard()
+                    || etag.is_some_and(|etag| if_none_match.iter().any(|tag| weak_eq(tag, &etag)))
+            }
+            None => match (if_modified_since, LastModified::from_headers(&res)) {
+                (Some(since), Ok(Some(modified))) => modified.modified() <= since.modified(),
+                _ => false,
+            },
+        };
+        if !not_modified {
+            return Ok(res);
+        }
+
+        let mut not_modified = Response::new(StatusCode::NotModified);
+        for name in KEPT_HEADERS {
+            if let Some(values) = res.header(name) {
+                not_modified.insert_header(name, values);
+            }
+        }
+        Ok(not_modified)
+    }
+}
+
+impl Default for ConditionalMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compare two ETags with the weak comparison `If-None-Match` uses.
+fn weak_eq(a: &ETag, b: &ETag) -> bool {
+    fn tag(etag: &ETag) -> &str {
+        match etag {
+            ETag::Strong(tag) | ETag::Weak(tag) => tag,
+        }
+    }
+    tag(a) == tag(b)
+}
+
+/// Hash a body into an ETag value, with the 64-bit FNV-1a hash.
+fn hash(bytes: &[u8]) -> String {
+    let mut hash = Fnv1a::new();
+    hash.write(bytes);
+    format!("{:x}-{:016x}", bytes.len(), hash.finish())
+}
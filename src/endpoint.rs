@@ -44,6 +44,9 @@ use crate::{Middleware, Request, Response};
 /// ```
 ///
 /// Tide routes will also accept endpoints with `Fn` signatures of this form, but using the `async` keyword has better ergonomics.
+///
+/// Functions taking typed extractors instead of a `Request` can be used as endpoints by wrapping
+/// them with [`extract::handler`](crate::extract::handler).
 #[async_trait]
 pub trait Endpoint<State: Clone + Send + Sync + 'static>: Send + Sync + 'static {
     /// Invoke the endpoint within the given context
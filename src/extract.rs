@@ -0,0 +1,361 @@
+//! Typed extraction of request data for endpoints.
+//!
+//! Endpoints usually take a [`Request`], and read what they need from it.
+//! Wrapping a function with [`handler`] lets it take extractors instead,
+//! which each pull a piece of the request into a typed argument and reject
+//! the request with an appropriate status code when they cannot:
+//!
+//! ```no_run
+//! # use async_std::task::block_on;
+//! # fn main() -> Result<(), std::io::Error> { block_on(async {
+//! #
+//! use tide::extract::{handler, Json, Path, State};
+//! use tide::prelude::*;
+//!
+//! #[derive(Deserialize)]
+//! struct Params {
+//!     id: u64,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Rename {
+//!     name: String,
+//! }
+//!
+//! async fn rename(
+//!     Path(params): Path<Params>,
+//!     Json(rename): Json<Rename>,
+//!     State(prefix): State<String>,
+//! ) -> tide::Result<String> {
+//!     Ok(format!("{}{} is now {}", prefix, params.id, rename.name))
+//! }
+//!
+//! let mut app = tide::with_state(String::from("user #"));
+//! app.at("/users/:id").put(handler(rename));
+//! app.listen("127.0.0.1:8080").await?;
+//! #
+//! # Ok(()) }) }
+//! ```
+//!
+//! Extractors run in the order of the function arguments. Only one of them
+//! can read the request body.
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::marker::PhantomData;
+
+use crate::http::headers::{self, HeaderName, Headers};
+use crate::http::{auth, cache, conditional, content, other, Mime};
+use crate::{Endpoint, Request, Response, StatusCode};
+
+/// Types that can be extracted from a request, to be taken as an argument
+/// by an endpoint wrapped with [`handler`].
+///
+/// # Examples
+///
+/// ```
+/// use tide::extract::FromRequest;
+/// use tide::{Request, StatusCode};
+///
+/// /// The id of the user, set by an authentication middleware.
+/// #[derive(Clone)]
+/// struct UserId(u64);
+///
+/// #[tide::utils::async_trait]
+/// impl<S: Clone + Send + Sync + 'static> FromRequest<S> for UserId {
+///     async fn from_request(req: &mut Request<S>) -> tide::Result<Self> {
+///         req.ext::<UserId>()
+///             .cloned()
+///             .ok_or_else(|| tide::Error::from_str(StatusCode::Unauthorized, "Not logged in"))
+///     }
+/// }
+///
+/// let mut app = tide::new();
+/// app.at("/me").get(tide::extract::handler(|UserId(id): UserId| async move {
+///     Ok(format!("User #{}", id))
+/// }));
+/// ```
+#[async_trait]
+pub trait FromRequest<S: Clone + Send + Sync + 'static>: Sized + Send + 'static {
+    /// Extract `Self` from the request.
+    ///
+    /// # Errors
+    ///
+    /// The returned error becomes the response, so it should carry a
+    /// status code describing why the request was rejected.
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self>;
+}
+
+/// Wrap a function taking extractors into an [`Endpoint`].
+///
+/// The function may take up to eight arguments implementing [`FromRequest`],
+/// and return a `Future` resolving to a `tide::Result` of anything
+/// convertible into a [`Response`], just like a regular endpoint. Failing
+/// extractors respond with their error, without calling the function.
+///
+/// A wrapper is needed because the endpoint implementation for functions
+/// taking a [`Request`] is what lets closures such as `|_| async { .. }`
+/// be used as endpoints without annotating their argument.
+///
+/// See the [module documentation](self) for an example.
+pub fn handler<F, Args>(f: F) -> Handler<F, Args> {
+    Handler {
+        f,
+        args: PhantomData,
+    }
+}
+
+/// A function taking extractors, made into an [`Endpoint`] by [`handler`].
+pub struct Handler<F, Args> {
+    f: F,
+    args: PhantomData<fn() -> Args>,
+}
+
+impl<F, Args> std::fmt::Debug for Handler<F, Args> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Handler").finish()
+    }
+}
+
+macro_rules! impl_handler {
+    ($($T:ident),*) => {
+        #[async_trait]
+        impl<S, F, Fut, Res, $($T),*> Endpoint<S> for Handler<F, ($($T,)*)>
+        where
+            S: Clone + Send + Sync + 'static,
+            F: Fn($($T),*) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = crate::Result<Res>> + Send + 'static,
+            Res: Into<Response> + 'static,
+            $($T: FromRequest<S>,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            async fn call(&self, mut req: Request<S>) -> crate::Result {
+                $(let $T = $T::from_request(&mut req).await?;)*
+                let res = (self.f)($($T),*).await?;
+                Ok(res.into())
+            }
+        }
+    };
+}
+
+impl_handler!();
+impl_handler!(T1);
+impl_handler!(T1, T2);
+impl_handler!(T1, T2, T3);
+impl_handler!(T1, T2, T3, T4);
+impl_handler!(T1, T2, T3, T4, T5);
+impl_handler!(T1, T2, T3, T4, T5, T6);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7);
+impl_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Is the request body of the given type? Types are matched on their
+/// essence, and `application/json` also matches `+json` subtypes.
+fn has_content_type(req: &Request<impl Clone + Send + Sync + 'static>, essence: &str) -> bool {
+    let mime: Option<Mime> = req.content_type();
+    mime.is_some_and(|mime| {
+        mime.essence() == essence
+            || (essence == "application/json" && mime.subtype().ends_with("+json"))
+    })
+}
+
+/// Extract the request body as JSON.
+///
+/// Responds with `415 Unsupported Media Type` unless the request has a JSON
+/// `Content-Type`, `400 Bad Request` if the body is not valid JSON, and
+/// `422 Unprocessable Entity` if it does not deserialize into `T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Json<T>
+where
+    S: Clone + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        if !has_content_type(req, "application/json") {
+            return Err(crate::Error::from_str(
+                StatusCode::UnsupportedMediaType,
+                "Expected a request body with content type `application/json`",
+            ));
+        }
+        let body = req.body_bytes().await?;
+        serde_json::from_slice(&body).map(Json).map_err(|e| {
+            let status = match e.classify() {
+                serde_json::error::Category::Data => StatusCode::UnprocessableEntity,
+                _ => StatusCode::BadRequest,
+            };
+            crate::Error::from_str(status, format!("Invalid JSON body: {}", e))
+        })
+    }
+}
+
+/// Extract the request body as an `application/x-www-form-urlencoded` form,
+/// as with [`Request::body_form`].
+///
+/// Responds with `415 Unsupported Media Type` unless the request has that
+/// `Content-Type`, and `422 Unprocessable Entity` if the form does not
+/// deserialize into `T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Form<T>
+where
+    S: Clone + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        if !has_content_type(req, "application/x-www-form-urlencoded") {
+            return Err(crate::Error::from_str(
+                StatusCode::UnsupportedMediaType,
+                "Expected a request body with content type `application/x-www-form-urlencoded`",
+            ));
+        }
+        req.body_form().await.map(Form)
+    }
+}
+
+/// Extract the URL query, as with [`Request::query`].
+///
+/// Responds with `400 Bad Request` if the query does not deserialize into
+/// `T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Query<T>
+where
+    S: Clone + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        req.query().map(Query).map_err(|mut e| {
+            e.set_status(StatusCode::BadRequest);
+            e
+        })
+    }
+}
+
+/// Extract the route parameters, as with [`Request::params`].
+///
+/// Responds with `400 Bad Request` if the parameters do not deserialize into
+/// `T`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Path<T>
+where
+    S: Clone + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        req.params().map(Path)
+    }
+}
+
+/// Extract a clone of the server state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State<S>(pub S);
+
+#[async_trait]
+impl<S> FromRequest<S> for State<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        Ok(State(req.state().clone()))
+    }
+}
+
+/// Typed headers that can be extracted with [`Header`].
+pub trait TypedHeader: Sized + Send + 'static {
+    /// The name of the header.
+    const NAME: HeaderName;
+
+    /// Parse the header from the request headers, returning `None` if it is
+    /// absent.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the header is present but invalid.
+    fn from_headers(headers: &Headers) -> crate::Result<Option<Self>>;
+}
+
+macro_rules! impl_typed_header {
+    ($($ty:ty => $name:ident,)*) => {
+        $(
+            impl TypedHeader for $ty {
+                const NAME: HeaderName = headers::$name;
+
+                fn from_headers(headers: &Headers) -> crate::Result<Option<Self>> {
+                    <$ty>::from_headers(headers)
+                }
+            }
+        )*
+    };
+}
+
+impl_typed_header! {
+    auth::Authorization => AUTHORIZATION,
+    auth::BasicAuth => AUTHORIZATION,
+    cache::CacheControl => CACHE_CONTROL,
+    conditional::IfMatch => IF_MATCH,
+    conditional::IfModifiedSince => IF_MODIFIED_SINCE,
+    conditional::IfNoneMatch => IF_NONE_MATCH,
+    conditional::IfUnmodifiedSince => IF_UNMODIFIED_SINCE,
+    content::Accept => ACCEPT,
+    content::AcceptEncoding => ACCEPT_ENCODING,
+    content::ContentLength => CONTENT_LENGTH,
+    content::ContentType => CONTENT_TYPE,
+    other::Date => DATE,
+    other::Expect => EXPECT,
+}
+
+/// Extract a typed header.
+///
+/// Responds with `400 Bad Request` if the header is missing or invalid.
+/// Take an `Option<Header<T>>` to accept requests without the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header<T>(pub T);
+
+fn header<T: TypedHeader>(headers: &Headers) -> crate::Result<Option<T>> {
+    T::from_headers(headers).map_err(|e| {
+        crate::Error::from_str(
+            StatusCode::BadRequest,
+            format!("Invalid header `{}`: {}", T::NAME, e),
+        )
+    })
+}
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Header<T>
+where
+    S: Clone + Send + Sync + 'static,
+    T: TypedHeader,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        let headers: &Headers = req.as_ref();
+        match header(headers)? {
+            Some(value) => Ok(Header(value)),
+            None => Err(crate::Error::from_str(
+                StatusCode::BadRequest,
+                format!("Missing header `{}`", T::NAME),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Option<Header<T>>
+where
+    S: Clone + Send + Sync + 'static,
+    T: TypedHeader,
+{
+    async fn from_request(req: &mut Request<S>) -> crate::Result<Self> {
+        let headers: &Headers = req.as_ref();
+        Ok(header(headers)?.map(Header))
+    }
+}
@@ -0,0 +1,95 @@
+use super::StaticFs;
+use crate::utils::Fnv1a;
+
+use async_std::io::{self, ReadExt};
+use std::collections::HashMap;
+
+/// The fingerprinted names of the files of a directory, computed from their
+/// contents.
+#[derive(Debug, Default)]
+pub(crate) struct Manifest {
+    /// The fingerprinted path of each file.
+    hashed: HashMap<String, String>,
+    /// The path of the file each fingerprinted path stands for.
+    originals: HashMap<String, String>,
+}
+
+impl Manifest {
+    /// Hash the contents of every file of `fs`, leaving out hidden files and
+    /// directories.
+    pub(crate) async fn build(fs: &dyn StaticFs) -> io::Result<Self> {
+        let mut manifest = Self::default();
+        let mut dirs = vec![String::new()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs.read_dir(&dir).await? {
+                if entry.name().starts_with('.') {
+                    continue;
+                }
+                let path = format!("{}{}", dir, entry.name());
+                if entry.metadata().is_dir() {
+                    dirs.push(format!("{}/", path));
+                    continue;
+                }
+                let hash = hash_file(fs, &path).await?;
+                let hashed = hashed_path(&path, hash);
+                manifest.originals.insert(hashed.clone(), path.clone());
+                manifest.hashed.insert(path, hashed);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// The path of the file a fingerprinted path stands for.
+    pub(crate) fn original(&self, hashed: &str) -> Option<&str> {
+        self.originals.get(hashed).map(String::as_str)
+    }
+
+    /// The paths of the files, with their fingerprinted paths.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.hashed
+            .iter()
+            .map(|(path, hashed)| (path.as_str(), hashed.as_str()))
+    }
+}
+
+async fn hash_file(fs: &dyn StaticFs, path: &str) -> io::Result<u64> {
+    let mut file = fs.open(path).await?;
+    let mut hash = Fnv1a::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(hash.finish());
+        }
+        hash.write(&buf[..n]);
+    }
+}
+
+/// Insert the first 32 bits of a hash before the extension of a path, as in
+/// `js/app.3f9a1c2b.js`.
+fn hashed_path(path: &str, hash: u64) -> String {
+    let fingerprint = format!("{:08x}", hash >> 32);
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (&path[..=dir.len()], name),
+        None => ("", path),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{}{}.{}.{}", dir, stem, fingerprint, extension)
+        }
+        _ => format!("{}{}.{}", dir, name, fingerprint),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inserts_fingerprints() {
+        let hash = 0x3f9a_1c2b_0000_0000;
+        assert_eq!(hashed_path("app.js", hash), "app.3f9a1c2b.js");
+        assert_eq!(hashed_path("js/lib.min.js", hash), "js/lib.min.3f9a1c2b.js");
+        assert_eq!(hashed_path("a.b/LICENSE", hash), "a.b/LICENSE.3f9a1c2b");
+    }
+}
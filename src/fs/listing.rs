@@ -0,0 +1,246 @@
+use crate::http::conditional::LastModified;
+use crate::http::content::Accept;
+use crate::http::{headers, mime};
+use crate::{Body, Request, Response, StatusCode};
+
+use super::StaticFs;
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::json;
+use std::fmt::Write;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Characters percent-encoded when a file name is used as a relative link.
+const NAME: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// An entry of a directory listing.
+#[derive(Debug)]
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// The column a listing is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Name,
+    Size,
+    Modified,
+}
+
+/// Respond with a listing of the directory at `path` of `fs`, as
+/// HTML or, if the request prefers it, JSON. Dotfiles are left out unless
+/// `hidden` is set.
+pub(crate) async fn respond<State>(
+    req: &Request<State>,
+    fs: &dyn StaticFs,
+    path: &str,
+    hidden: bool,
+) -> crate::Result<Response> {
+    let mut entries = read_entries(fs, path, hidden).await?;
+
+    let mut sort = Sort::Name;
+    let mut descending = false;
+    for (key, value) in req.url().query_pairs() {
+        match (&*key, &*value) {
+            ("sort", "name") => sort = Sort::Name,
+            ("sort", "size") => sort = Sort::Size,
+            ("sort", "modified") => sort = Sort::Modified,
+            ("order", "asc") => descending = false,
+            ("order", "desc") => descending = true,
+            _ => {}
+        }
+    }
+    entries.sort_by(|a, b| {
+        let ordering = match sort {
+            Sort::Name => a.name.cmp(&b.name),
+            Sort::Size => a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name)),
+            Sort::Modified => a
+                .modified
+                .cmp(&b.modified)
+                .then_with(|| a.name.cmp(&b.name)),
+        };
+        let ordering = if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        // Directories always come first.
+        b.is_dir.cmp(&a.is_dir).then(ordering)
+    });
+
+    let mut res = Response::new(StatusCode::Ok);
+    res.insert_header(headers::VARY, "Accept");
+    if prefers_json(req) {
+        res.set_body(Body::from_json(&json_listing(&entries))?);
+    } else {
+        let root = path.is_empty();
+        let html = html_listing(req.url().path(), root, &entries, sort, descending);
+        let mut body = Body::from_string(html);
+        body.set_mime(mime::HTML);
+        res.set_body(body);
+    }
+    Ok(res)
+}
+
+/// Read the entries of the directory at `path`.
+async fn read_entries(fs: &dyn StaticFs, path: &str, hidden: bool) -> io::Result<Vec<Entry>> {
+    let entries = fs.read_dir(path).await?;
+    let entries = entries
+        .into_iter()
+        .filter(|entry| hidden || !entry.name().starts_with('.'))
+        .map(|entry| Entry {
+            name: entry.name().to_owned(),
+            is_dir: entry.metadata().is_dir(),
+            size: entry.metadata().len(),
+            modified: entry.metadata().modified(),
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Whether the request's `Accept` header prefers JSON to HTML.
+fn prefers_json<State>(req: &Request<State>) -> bool {
+    let accept = match Accept::from_headers(req) {
+        Ok(Some(accept)) => accept,
+        _ => return false,
+    };
+    // The highest weight given to any of the media types.
+    let weight = |essences: &[&str]| {
+        accept
+            .iter()
+            .filter(|proposal| essences.contains(&proposal.essence()))
+            .map(|proposal| proposal.weight().unwrap_or(1.0))
+            .fold(0.0, f32::max)
+    };
+    let json = weight(&["application/json"]);
+    json > 0.0 && json > weight(&["text/html", "text/*", "*/*"])
+}
+
+fn json_listing(entries: &[Entry]) -> serde_json::Value {
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "name": entry.name,
+                "type": if entry.is_dir { "directory" } else { "file" },
+                "size": entry.size,
+                "modified": entry.modified.and_then(|modified| {
+                    modified.duration_since(UNIX_EPOCH).ok().map(|since| since.as_secs())
+                }),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!(entries)
+}
+
+/// Render a listing titled with the request path `path`. The root of the
+/// served directory has no link to its parent.
+fn html_listing(path: &str, root: bool, entries: &[Entry], sort: Sort, descending: bool) -> String {
+    let title = format!("Index of {}", escape(path));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n<table>\n<thead>\n<tr>",
+        title
+    );
+    for (column, label) in [
+        (Sort::Name, "Name"),
+        (Sort::Size, "Size"),
+        (Sort::Modified, "Modified"),
+    ] {
+        // Clicking the current column again reverses its order.
+        let order = if column == sort && !descending {
+            "desc"
+        } else {
+            "asc"
+        };
+        let key = match column {
+            Sort::Name => "name",
+            Sort::Size => "size",
+            Sort::Modified => "modified",
+        };
+        let _ = write!(
+            html,
+            "<th><a href=\"?sort={}&amp;order={}\">{}</a></th>",
+            key, order, label
+        );
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    if !root {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let slash = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir {
+            String::new()
+        } else {
+            entry.size.to_string()
+        };
+        let modified = entry
+            .modified
+            .map(|modified| LastModified::new(modified).value().to_string())
+            .unwrap_or_default();
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>",
+            utf8_percent_encode(&entry.name, NAME),
+            slash,
+            escape(&entry.name),
+            slash,
+            size,
+            modified
+        );
+    }
+    html.push_str("</tbody>\n</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Escape text for use in HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn escapes_names() {
+        let entries = [Entry {
+            name: "<a b>.txt".to_string(),
+            is_dir: false,
+            size: 3,
+            modified: None,
+        }];
+        let html = html_listing("/files/", true, &entries, Sort::Name, false);
+        assert!(html.contains("<a href=\"%3Ca%20b%3E.txt\">&lt;a b&gt;.txt</a>"));
+        assert!(html.contains("<a href=\"?sort=name&amp;order=desc\">Name</a>"));
+        assert!(html.contains("<a href=\"?sort=size&amp;order=asc\">Size</a>"));
+    }
+
+    #[test]
+    fn links_to_parents_below_the_root() {
+        let html = html_listing("/files/", true, &[], Sort::Name, false);
+        assert!(!html.contains("../"));
+        let html = html_listing("/files/docs/", false, &[], Sort::Name, false);
+        assert!(html.contains("<a href=\"../\">../</a>"));
+    }
+}
@@ -0,0 +1,281 @@
+use super::{DirEntry, Metadata, StaticFile, StaticFs};
+
+use async_std::io::{self, Cursor};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::FromIterator;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// A read-only filesystem kept in memory, such as files embedded in the
+/// binary with `include_bytes!`.
+///
+/// Directories are implied by the paths of the files. All files share the
+/// modification time of the moment the `MemoryFs` was created, so their
+/// `ETag` and `Last-Modified` validators change when the server restarts.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
+/// #
+/// use tide::fs::MemoryFs;
+///
+/// let assets = MemoryFs::new()
+///     .file("index.html", &b"<script src=\"js/app.js\"></script>"[..])
+///     .file("js/app.js", &b"console.log(\"Hello\");"[..]);
+///
+/// let mut app = tide::new();
+/// app.at("/*").serve_dir(assets)?;
+/// app.listen("localhost:8080").await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemoryFs {
+    tree: Tree,
+    modified: SystemTime,
+}
+
+impl MemoryFs {
+    /// Create a new, empty instance of `MemoryFs`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            tree: Tree::default(),
+            modified: SystemTime::now(),
+        }
+    }
+
+    /// Add a file at `path`, replacing any file already there.
+    #[must_use]
+    pub fn file(mut self, path: &str, contents: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    fn insert(&mut self, path: &str, contents: impl Into<Cow<'static, [u8]>>) {
+        let contents = Arc::new(contents.into());
+        let range = 0..contents.len();
+        self.tree
+            .insert_file(path, contents, range, Some(self.modified));
+    }
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, C> FromIterator<(P, C)> for MemoryFs
+where
+    P: AsRef<str>,
+    C: Into<Cow<'static, [u8]>>,
+{
+    fn from_iter<I: IntoIterator<Item = (P, C)>>(files: I) -> Self {
+        let mut fs = Self::new();
+        for (path, contents) in files {
+            fs.insert(path.as_ref(), contents);
+        }
+        fs
+    }
+}
+
+#[async_trait::async_trait]
+impl StaticFs for MemoryFs {
+    async fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        self.tree.metadata(path)
+    }
+
+    async fn open(&self, path: &str) -> io::Result<StaticFile> {
+        self.tree.open(path)
+    }
+
+    async fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        self.tree.read_dir(path)
+    }
+}
+
+/// Files held in memory, as ranges of shared buffers, and the directories
+/// containing them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Tree {
+    files: BTreeMap<String, File>,
+    dirs: BTreeMap<String, Option<SystemTime>>,
+}
+
+#[derive(Debug, Clone)]
+struct File {
+    data: Arc<Cow<'static, [u8]>>,
+    range: Range<usize>,
+    modified: Option<SystemTime>,
+}
+
+/// A range of a shared buffer, to read with a `Cursor`.
+#[derive(Debug)]
+struct Slice(Arc<Cow<'static, [u8]>>, Range<usize>);
+
+impl AsRef<[u8]> for Slice {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[self.1.clone()]
+    }
+}
+
+impl Tree {
+    /// Add the file at `path`, whose contents are `range` of `data`. Paths
+    /// with `..` segments are ignored.
+    pub(crate) fn insert_file(
+        &mut self,
+        path: &str,
+        data: Arc<Cow<'static, [u8]>>,
+        range: Range<usize>,
+        modified: Option<SystemTime>,
+    ) {
+        let path = match normalize(path) {
+            Some(path) if !path.is_empty() => path,
+            _ => return,
+        };
+        self.insert_parents(&path, modified);
+        self.dirs.remove(&path);
+        self.files.insert(
+            path,
+            File {
+                data,
+                range,
+                modified,
+            },
+        );
+    }
+
+    /// Add the directory at `path`. Paths with `..` segments are ignored.
+    pub(crate) fn insert_dir(&mut self, path: &str, modified: Option<SystemTime>) {
+        let path = match normalize(path) {
+            Some(path) => path,
+            None => return,
+        };
+        if self.files.contains_key(&path) {
+            return;
+        }
+        self.insert_parents(&path, modified);
+        self.dirs.insert(path, modified);
+    }
+
+    /// Add the directories containing `path` that are missing.
+    fn insert_parents(&mut self, path: &str, modified: Option<SystemTime>) {
+        let mut parent = path;
+        while let Some((dir, _)) = parent.rsplit_once('/') {
+            self.dirs.entry(dir.to_string()).or_insert(modified);
+            parent = dir;
+        }
+        self.dirs.entry(String::new()).or_insert(modified);
+    }
+
+    pub(crate) fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let trailing_slash = path.ends_with('/');
+        let path = normalize(path).ok_or(io::ErrorKind::PermissionDenied)?;
+        if let Some(modified) = self.dirs.get(&path) {
+            return Ok(Metadata::dir(*modified));
+        }
+        match self.files.get(&path) {
+            Some(file) if !trailing_slash => {
+                Ok(Metadata::file(file.range.len() as u64, file.modified))
+            }
+            _ => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    pub(crate) fn open(&self, path: &str) -> io::Result<StaticFile> {
+        let metadata = self.metadata(path)?;
+        let path = normalize(path).ok_or(io::ErrorKind::PermissionDenied)?;
+        let file = match self.files.get(&path) {
+            Some(file) => file,
+            None => return Err(io::ErrorKind::NotFound.into()),
+        };
+        let slice = Slice(file.data.clone(), file.range.clone());
+        Ok(StaticFile::new(Cursor::new(slice), metadata))
+    }
+
+    pub(crate) fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        if !self.metadata(path)?.is_dir() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        let path = normalize(path).ok_or(io::ErrorKind::PermissionDenied)?;
+        let prefix = if path.is_empty() {
+            path
+        } else {
+            format!("{}/", path)
+        };
+
+        let mut names = BTreeSet::new();
+        let children = self.dirs.keys().chain(self.files.keys());
+        for child in children.filter_map(|child| child.strip_prefix(&prefix)) {
+            if !child.is_empty() && !child.contains('/') {
+                names.insert(child);
+            }
+        }
+        names
+            .into_iter()
+            .map(|name| {
+                let metadata = self.metadata(&format!("{}{}", prefix, name))?;
+                Ok(DirEntry::new(name, metadata))
+            })
+            .collect()
+    }
+}
+
+/// Normalize a path to its segments joined with `/`, without empty or `.`
+/// segments, or `None` if it has `..` segments.
+fn normalize(path: &str) -> Option<String> {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            segment => segments.push(segment),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use async_std::prelude::*;
+
+    #[async_std::test]
+    async fn implies_directories() -> io::Result<()> {
+        let fs: MemoryFs = vec![("/a/b/c.txt", &b"abc"[..]), ("./a/d.txt", &b"d"[..])]
+            .into_iter()
+            .collect();
+
+        assert!(fs.metadata("").await?.is_dir());
+        assert!(fs.metadata("a/").await?.is_dir());
+        assert!(fs.metadata("a/b").await?.is_dir());
+        assert_eq!(fs.metadata("a/b/c.txt").await?.len(), 3);
+        let entries = fs.read_dir("a").await?;
+        let names: Vec<_> = entries.iter().map(DirEntry::name).collect();
+        assert_eq!(names, ["b", "d.txt"]);
+
+        let mut contents = String::new();
+        fs.open("a//b/./c.txt")
+            .await?
+            .read_to_string(&mut contents)
+            .await?;
+        assert_eq!(contents, "abc");
+
+        let kind = |result: io::Result<Metadata>| result.unwrap_err().kind();
+        assert_eq!(
+            kind(fs.metadata("a/b/c.txt/").await),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(kind(fs.metadata("x").await), io::ErrorKind::NotFound);
+        assert_eq!(
+            kind(fs.metadata("a/../a").await),
+            io::ErrorKind::PermissionDenied
+        );
+        assert!(fs.open("a").await.is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,117 @@
+//! Serving static files.
+//!
+//! [`Route::serve_dir`](crate::Route::serve_dir) and
+//! [`Route::serve_file`](crate::Route::serve_file) serve files from disk.
+//! [`ServeDirOptions`] configures how
+//! [`Route::serve_dir_with`](crate::Route::serve_dir_with) serves a directory.
+//!
+//! Directories can also be served from any [`StaticFs`], such as a
+//! [`MemoryFs`] of files embedded in the binary, or a [`TarFs`] archive.
+
+mod fingerprint;
+mod listing;
+mod memory;
+mod options;
+mod range;
 mod serve_dir;
 mod serve_file;
+mod static_fs;
+mod tar;
+
+pub use memory::MemoryFs;
+pub use options::ServeDirOptions;
+pub use static_fs::{DirEntry, IntoStaticFs, Metadata, StaticFile, StaticFs};
+pub use tar::TarFs;
 
 pub(crate) use serve_dir::ServeDir;
 pub(crate) use serve_file::ServeFile;
+
+use range::{ByteRanges, Requested};
+
+use crate::http::conditional::{ETag, LastModified};
+use crate::http::{headers, Mime};
+use crate::{Body, Request, Response, StatusCode};
+
+use async_std::io::{BufReader, Read, Seek};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Respond with the parts of an open file of `len` bytes requested by `req`:
+/// the whole file, the ranges of its `Range` header, or a `416 Range Not
+/// Satisfiable` error.
+pub(crate) fn file_response<State, F>(
+    req: &Request<State>,
+    file: F,
+    len: u64,
+    mime: Mime,
+    modified: Option<SystemTime>,
+) -> Response
+where
+    F: Read + Seek + Unpin + Send + Sync + 'static,
+{
+    let mut res = Response::new(StatusCode::Ok);
+    res.insert_header(headers::ACCEPT_RANGES, "bytes");
+    set_validators(&mut res, len, modified);
+
+    match range::requested(req, &res, len) {
+        Requested::Full => {
+            let mut body = Body::from_reader(BufReader::new(file), Some(len as usize));
+            body.set_mime(mime);
+            res.set_body(body);
+        }
+        Requested::Unsatisfiable => {
+            res.set_status(StatusCode::RequestedRangeNotSatisfiable);
+            res.insert_header(headers::CONTENT_RANGE, format!("bytes */{}", len));
+        }
+        Requested::Ranges(ranges) if ranges.len() == 1 => {
+            let (start, end) = ranges[0];
+            let body = ByteRanges::single(file, (start, end));
+            let body_len = body.len() as usize;
+            let mut body = Body::from_reader(BufReader::new(body), Some(body_len));
+            body.set_mime(mime);
+            res.set_status(StatusCode::PartialContent);
+            res.set_body(body);
+            res.insert_header(
+                headers::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, len),
+            );
+        }
+        Requested::Ranges(ranges) => {
+            let boundary = boundary(len);
+            let body = ByteRanges::multipart(file, &ranges, len, &mime.to_string(), &boundary);
+            let body_len = body.len() as usize;
+            let mut body = Body::from_reader(BufReader::new(body), Some(body_len));
+            let content_type = format!("multipart/byteranges; boundary={}", boundary);
+            body.set_mime(content_type.parse::<Mime>().unwrap());
+            res.set_status(StatusCode::PartialContent);
+            res.set_body(body);
+        }
+    }
+    res
+}
+
+/// Set the `Last-Modified` and `ETag` validators of a response serving a file
+/// of `len` bytes, from its modification time.
+fn set_validators(res: &mut Response, len: u64, modified: Option<SystemTime>) {
+    let modified = match modified {
+        Some(modified) => modified,
+        None => return,
+    };
+    LastModified::new(modified).apply(&mut *res);
+
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let etag = format!(
+        "{:x}.{:x}-{:x}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos(),
+        len
+    );
+    ETag::new(etag).apply(res);
+}
+
+/// A `multipart/byteranges` boundary, unlikely to appear in the file.
+fn boundary(len: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{:x}{:08x}{:x}", now.as_secs(), now.subsec_nanos(), len)
+}
@@ -0,0 +1,216 @@
+use crate::http::{mime, Mime};
+
+use std::collections::HashMap;
+
+/// Content types of common static files, by extension, beyond the ones
+/// `Mime::from_extension` knows.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("avif", "image/avif"),
+    ("csv", "text/csv;charset=utf-8"),
+    ("gif", "image/gif"),
+    ("gz", "application/gzip"),
+    ("htm", "text/html;charset=utf-8"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("map", "application/json"),
+    ("md", "text/markdown;charset=utf-8"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("ogg", "audio/ogg"),
+    ("otf", "font/otf"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain;charset=utf-8"),
+    ("wasm", "application/wasm"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webmanifest", "application/manifest+json"),
+    ("webp", "image/webp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("zip", "application/zip"),
+];
+
+/// Options for serving a directory with
+/// [`Route::serve_dir_with`](crate::Route::serve_dir_with).
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
+/// #
+/// use tide::fs::ServeDirOptions;
+/// use tide::http::mime;
+///
+/// let options = ServeDirOptions::new()
+///     .index_files(["index.html", "index.htm"])
+///     .mime_type("data", mime::JSON);
+///
+/// let mut app = tide::new();
+/// app.at("/public/*").serve_dir_with("public/", options)?;
+/// app.listen("localhost:8080").await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeDirOptions {
+    pub(crate) mime_types: HashMap<String, Mime>,
+    pub(crate) index_files: Vec<String>,
+    pub(crate) listing: bool,
+    pub(crate) hidden: bool,
+    pub(crate) precompressed: bool,
+    pub(crate) fallback: Option<String>,
+    pub(crate) fingerprint: bool,
+}
+
+impl ServeDirOptions {
+    /// Create a new instance of `ServeDirOptions`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            mime_types: HashMap::new(),
+            index_files: vec!["index.html".to_string()],
+            listing: false,
+            hidden: false,
+            precompressed: false,
+            fallback: None,
+            fingerprint: false,
+        }
+    }
+
+    /// Serve files with the given extension, without the leading dot, with
+    /// the given content type.
+    ///
+    /// This overrides the built-in mapping for common web file types. Files
+    /// with an unknown extension are served as `application/octet-stream`.
+    #[must_use]
+    pub fn mime_type(mut self, extension: &str, mime: Mime) -> Self {
+        self.mime_types.insert(extension.to_ascii_lowercase(), mime);
+        self
+    }
+
+    /// Set the files served for requests to a directory, in order of
+    /// preference. Defaults to `index.html`; an empty list disables index
+    /// files.
+    #[must_use]
+    pub fn index_files<I>(mut self, files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.index_files = files.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// List the contents of directories without an index file. Defaults to
+    /// `false`, so such requests are answered with `404 Not Found`.
+    ///
+    /// Listings are rendered as an HTML page that can be sorted by name, size
+    /// or modification time with the `sort` and `order` query parameters,
+    /// or as a JSON array if the request's `Accept` header prefers
+    /// `application/json`.
+    #[must_use]
+    pub fn listing(mut self, listing: bool) -> Self {
+        self.listing = listing;
+        self
+    }
+
+    /// Serve hidden files and directories, whose name starts with a dot, and
+    /// include them in directory listings. Defaults to `false`, so requests
+    /// for paths with a hidden segment, such as `.git/config`, are answered
+    /// with `404 Not Found`.
+    #[must_use]
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Serve precompressed siblings of files, such as `app.js.br` or
+    /// `app.js.gz` for `app.js`, to clients whose `Accept-Encoding` header
+    /// allows it. Defaults to `false`.
+    ///
+    /// Brotli (`.br`) siblings are preferred to gzip (`.gz`) ones unless the
+    /// client prefers gzip. The response keeps the content type of the
+    /// original file and has a `Content-Encoding` header.
+    #[must_use]
+    pub fn precompressed(mut self, precompressed: bool) -> Self {
+        self.precompressed = precompressed;
+        self
+    }
+
+    /// Serve the given file, relative to the directory, for navigation
+    /// requests to missing paths, as single-page applications with
+    /// client-side routing need.
+    ///
+    /// Navigation requests are `GET` or `HEAD` requests whose `Accept` header
+    /// includes `text/html`. Requests for missing paths whose last segment
+    /// has an extension, such as `app.js`, still get `404 Not Found`.
+    #[must_use]
+    pub fn fallback(mut self, file: impl Into<String>) -> Self {
+        self.fallback = Some(file.into());
+        self
+    }
+
+    /// Also serve every file under a fingerprinted name, with a hash of its
+    /// contents inserted before its extension, such as `app.3f9a1c2b.js` for
+    /// `app.js`. Defaults to `false`.
+    ///
+    /// The hashes are computed when the directory is mounted, blocking the
+    /// current thread while every file is read, and hidden files are left
+    /// out. Fingerprinted directories can't be mounted on routes with params. Fingerprinted names are served with
+    /// `Cache-Control: max-age=31536000, immutable`, since their contents
+    /// never change, and plain names with a short `max-age=60`. Links to the
+    /// fingerprinted names are built with
+    /// [`Request::asset_url`](crate::Request::asset_url).
+    #[must_use]
+    pub fn fingerprint(mut self, fingerprint: bool) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// The content type of a file, from the extension of its path.
+    pub(crate) fn mime_for(&self, path: &str) -> Mime {
+        let extension = match path
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit_once('.'))
+        {
+            Some((stem, extension)) if !stem.is_empty() => extension.to_ascii_lowercase(),
+            _ => return mime::BYTE_STREAM,
+        };
+        if let Some(mime) = self.mime_types.get(&extension) {
+            return mime.clone();
+        }
+        Mime::from_extension(&extension)
+            .or_else(|| {
+                MIME_TYPES
+                    .iter()
+                    .find(|(ext, _)| *ext == extension)
+                    .and_then(|(_, mime)| mime.parse().ok())
+            })
+            .unwrap_or(mime::BYTE_STREAM)
+    }
+}
+
+impl Default for ServeDirOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn infers_mime_types() {
+        let options = ServeDirOptions::new().mime_type("JS", mime::PLAIN);
+        assert_eq!(options.mime_for("index.html"), mime::HTML);
+        assert_eq!(options.mime_for("img/logo.PNG"), mime::PNG);
+        assert_eq!(options.mime_for("app.js"), mime::PLAIN);
+        assert_eq!(options.mime_for("v1.0/data"), mime::BYTE_STREAM);
+        assert_eq!(options.mime_for(".png"), mime::BYTE_STREAM);
+        assert_eq!(options.mime_for("font.woff2").essence(), "font/woff2");
+    }
+}
@@ -0,0 +1,303 @@
+use crate::http::{headers, Method};
+use crate::{Request, Response};
+
+use async_std::io::{self, Read, Seek, SeekFrom};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The `Range` request header, which http-types has no constant for.
+const RANGE: &str = "range";
+
+/// Requests for more ranges than this, satisfiable or not, are served the
+/// whole file.
+const MAX_RANGES: usize = 32;
+
+/// The part of a file a request asks for.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Requested {
+    /// The whole file.
+    Full,
+    /// Byte ranges, as inclusive bounds within the file, sorted and without
+    /// overlapping or adjacent ranges.
+    Ranges(Vec<(u64, u64)>),
+    /// Only ranges outside the file.
+    Unsatisfiable,
+}
+
+/// Find the ranges of a file of `len` bytes requested by `req`, given the
+/// response with the file's validators.
+pub(crate) fn requested<State>(req: &Request<State>, res: &Response, len: u64) -> Requested {
+    if !matches!(req.method(), Method::Get | Method::Head) {
+        return Requested::Full;
+    }
+    let range = match req.header(RANGE) {
+        Some(range) => range.last().as_str(),
+        None => return Requested::Full,
+    };
+
+    // A range is only served if the file did not change since the client got
+    // its validator.
+    if let Some(if_range) = req.header(headers::IF_RANGE) {
+        let if_range = if_range.last().as_str().trim();
+        let validator = if if_range.starts_with('"') {
+            res.header(headers::ETAG)
+        } else {
+            res.header(headers::LAST_MODIFIED)
+        };
+        match validator {
+            Some(validator) if validator.last().as_str() == if_range => {}
+            _ => return Requested::Full,
+        }
+    }
+
+    parse(range, len)
+}
+
+/// Parse a `Range` header. Malformed headers are ignored, as the spec allows,
+/// and overlapping or adjacent ranges are coalesced, so a file is never sent
+/// more than once in a response.
+fn parse(range: &str, len: u64) -> Requested {
+    let specs = match range.split_once('=') {
+        Some((unit, specs)) if unit.trim().eq_ignore_ascii_case("bytes") => specs,
+        _ => return Requested::Full,
+    };
+
+    let mut ranges = Vec::new();
+    let mut count = 0;
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        count += 1;
+        if count > MAX_RANGES {
+            return Requested::Full;
+        }
+        let (start, end) = match spec.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => return Requested::Full,
+        };
+        let range = match (start, end) {
+            ("", suffix) => match number(suffix) {
+                Some(suffix) if suffix > 0 && len > 0 => Some((len - suffix.min(len), len - 1)),
+                Some(_) => None,
+                None => return Requested::Full,
+            },
+            (start, "") => match number(start) {
+                Some(start) => (start < len).then(|| (start, len - 1)),
+                None => return Requested::Full,
+            },
+            (start, end) => match (number(start), number(end)) {
+                (Some(start), Some(end)) if start <= end => {
+                    (start < len).then(|| (start, end.min(len - 1)))
+                }
+                _ => return Requested::Full,
+            },
+        };
+        ranges.extend(range);
+    }
+
+    if count == 0 {
+        Requested::Full
+    } else if ranges.is_empty() {
+        Requested::Unsatisfiable
+    } else {
+        Requested::Ranges(coalesce(ranges))
+    }
+}
+
+/// Sort ranges and merge the ones that overlap or are adjacent.
+fn coalesce(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+    let mut coalesced: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match coalesced.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => coalesced.push((start, end)),
+        }
+    }
+    coalesced
+}
+
+/// Parse a non-negative decimal number, without a sign.
+fn number(s: &str) -> Option<u64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// A piece of a partial response body.
+#[derive(Debug)]
+enum Segment {
+    /// Bytes written out as they are, from the given position.
+    Bytes(Vec<u8>, usize),
+    /// `len` bytes of the file from `start`, once the file has been seeked
+    /// there.
+    File { start: u64, len: u64, seeked: bool },
+}
+
+/// A body made of ranges of a file, separated by multipart headers if there
+/// are several.
+#[derive(Debug)]
+pub(crate) struct ByteRanges<F> {
+    file: F,
+    segments: VecDeque<Segment>,
+}
+
+impl<F> ByteRanges<F> {
+    /// A body with the single range `start..=end` of the file.
+    pub(crate) fn single(file: F, (start, end): (u64, u64)) -> Self {
+        let mut segments = VecDeque::new();
+        segments.push_back(Segment::File {
+            start,
+            len: end - start + 1,
+            seeked: false,
+        });
+        Self { file, segments }
+    }
+
+    /// A `multipart/byteranges` body with the given ranges of a file of `len`
+    /// bytes, whose parts have the content type `mime`.
+    pub(crate) fn multipart(
+        file: F,
+        ranges: &[(u64, u64)],
+        len: u64,
+        mime: &str,
+        boundary: &str,
+    ) -> Self {
+        let mut segments = VecDeque::new();
+        for (start, end) in ranges {
+            let headers = format!(
+                "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                boundary, mime, start, end, len
+            );
+            segments.push_back(Segment::Bytes(headers.into_bytes(), 0));
+            segments.push_back(Segment::File {
+                start: *start,
+                len: end - start + 1,
+                seeked: false,
+            });
+        }
+        let end = format!("\r\n--{}--\r\n", boundary);
+        segments.push_back(Segment::Bytes(end.into_bytes(), 0));
+        Self { file, segments }
+    }
+
+    /// The length of the body in bytes.
+    pub(crate) fn len(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Bytes(bytes, _) => bytes.len() as u64,
+                Segment::File { len, .. } => *len,
+            })
+            .sum()
+    }
+}
+
+impl<F: Read + Seek + Unpin> Read for ByteRanges<F> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            match this.segments.front_mut() {
+                None => return Poll::Ready(Ok(0)),
+                Some(Segment::Bytes(bytes, pos)) => {
+                    if *pos == bytes.len() {
+                        this.segments.pop_front();
+                        continue;
+                    }
+                    let n = (bytes.len() - *pos).min(buf.len());
+                    buf[..n].copy_from_slice(&bytes[*pos..*pos + n]);
+                    *pos += n;
+                    return Poll::Ready(Ok(n));
+                }
+                Some(Segment::File { start, len, seeked }) => {
+                    if *len == 0 {
+                        this.segments.pop_front();
+                        continue;
+                    }
+                    if !*seeked {
+                        let seek = Pin::new(&mut this.file).poll_seek(cx, SeekFrom::Start(*start));
+                        futures_util::ready!(seek)?;
+                        *seeked = true;
+                    }
+                    let max = (*len).min(buf.len() as u64) as usize;
+                    let n = futures_util::ready!(
+                        Pin::new(&mut this.file).poll_read(cx, &mut buf[..max])
+                    )?;
+                    if n == 0 {
+                        return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                    }
+                    *len -= n as u64;
+                    return Poll::Ready(Ok(n));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_ranges() {
+        assert_eq!(parse("bytes=0-4", 10), Requested::Ranges(vec![(0, 4)]));
+        assert_eq!(parse("bytes=5-", 10), Requested::Ranges(vec![(5, 9)]));
+        assert_eq!(parse("bytes=-3", 10), Requested::Ranges(vec![(7, 9)]));
+        assert_eq!(parse("bytes=-30", 10), Requested::Ranges(vec![(0, 9)]));
+        assert_eq!(parse("bytes=8-20", 10), Requested::Ranges(vec![(8, 9)]));
+        assert_eq!(
+            parse("bytes=0-0, 10-20, -1", 10),
+            Requested::Ranges(vec![(0, 0), (9, 9)])
+        );
+    }
+
+    #[test]
+    fn rejects_unsatisfiable_ranges() {
+        assert_eq!(parse("bytes=10-", 10), Requested::Unsatisfiable);
+        assert_eq!(parse("bytes=-0", 10), Requested::Unsatisfiable);
+        assert_eq!(parse("bytes=0-", 0), Requested::Unsatisfiable);
+    }
+
+    #[test]
+    fn ignores_malformed_ranges() {
+        for range in [
+            "items=0-4",
+            "bytes=",
+            "bytes=4-2",
+            "bytes=a-b",
+            "bytes=+1-2",
+            "bytes=5",
+        ] {
+            assert_eq!(parse(range, 10), Requested::Full, "{}", range);
+        }
+        let many = vec!["0-0"; MAX_RANGES + 1].join(",");
+        assert_eq!(parse(&format!("bytes={}", many), 10), Requested::Full);
+        let many = vec!["20-"; MAX_RANGES + 1].join(",");
+        assert_eq!(parse(&format!("bytes={}", many), 10), Requested::Full);
+    }
+
+    #[test]
+    fn coalesces_ranges() {
+        let many = vec!["0-"; MAX_RANGES].join(",");
+        assert_eq!(
+            parse(&format!("bytes={}", many), 10),
+            Requested::Ranges(vec![(0, 9)])
+        );
+        assert_eq!(
+            parse("bytes=5-6, 0-1, 2-3", 10),
+            Requested::Ranges(vec![(0, 3), (5, 6)])
+        );
+        assert_eq!(
+            parse("bytes=0-4, 3-7, -1", 10),
+            Requested::Ranges(vec![(0, 7), (9, 9)])
+        );
+    }
+}
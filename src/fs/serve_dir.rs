@@ -1,56 +1,254 @@
+use super::fingerprint::Manifest;
+use super::{file_response, listing, ServeDirOptions, StaticFile, StaticFs};
+use crate::http::content::{Accept, AcceptEncoding, ContentEncoding, Encoding};
+use crate::http::{headers, Method, Mime};
 use crate::log;
-use crate::{Body, Endpoint, Request, Response, Result, StatusCode};
+use crate::{Endpoint, Redirect, Request, Response, Result, StatusCode};
 
-use async_std::io::BufReader;
+use percent_encoding::percent_decode_str;
+use std::io;
 
-use cap_async_std::fs;
+/// The `Cache-Control` header of files served under a fingerprinted name.
+const IMMUTABLE: &str = "max-age=31536000, immutable";
 
-pub(crate) struct ServeDir {
-    prefix: String,
-    dir: fs::Dir,
+/// The `Cache-Control` header of fingerprinted files served under their
+/// plain name, which can change at any deploy.
+const SHORT_LIVED: &str = "max-age=60";
+
+pub(crate) struct ServeDir<F> {
+    fs: F,
+    options: ServeDirOptions,
+    manifest: Manifest,
 }
 
-impl ServeDir {
+impl<F: StaticFs> ServeDir<F> {
     /// Create a new instance of `ServeDir`.
-    pub(crate) fn new(prefix: String, dir: fs::Dir) -> Self {
-        Self { prefix, dir }
+    pub(crate) fn new(fs: F, options: ServeDirOptions) -> Self {
+        Self {
+            fs,
+            options,
+            manifest: Manifest::default(),
+        }
+    }
+
+    /// Compute the fingerprinted names of the files, so they can be served.
+    pub(crate) async fn fingerprint(&mut self) -> io::Result<()> {
+        self.manifest = Manifest::build(&self.fs).await?;
+        Ok(())
+    }
+
+    /// The fingerprinted names of the files.
+    pub(crate) fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// Serve the file at `path`, or `None` if there is no such file.
+    ///
+    /// If precompressed files are enabled, the file's best sibling with an
+    /// encoding the client accepts is served instead.
+    async fn serve<State>(&self, req: &Request<State>, path: &str) -> Result<Option<Response>> {
+        let file = match self.open(path).await? {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let mime = self.options.mime_for(path);
+        if !self.options.precompressed {
+            let mut res = respond(req, file, mime);
+            if self.options.fingerprint {
+                res.insert_header(headers::CACHE_CONTROL, SHORT_LIVED);
+            }
+            return Ok(Some(res));
+        }
+
+        let mut res = None;
+        for (encoding, extension) in accepted_encodings(req) {
+            if let Some(sibling) = self.open(&format!("{}.{}", path, extension)).await? {
+                let mut sibling_res = respond(req, sibling, mime.clone());
+                ContentEncoding::new(encoding).apply(&mut sibling_res);
+                res = Some(sibling_res);
+                break;
+            }
+        }
+        let mut res = match res {
+            Some(res) => res,
+            None => respond(req, file, mime),
+        };
+        res.append_header(headers::VARY, "Accept-Encoding");
+        if self.options.fingerprint {
+            res.insert_header(headers::CACHE_CONTROL, SHORT_LIVED);
+        }
+        Ok(Some(res))
+    }
+
+    /// Serve the fallback file, if there is one, for a navigation request to
+    /// the missing `path`.
+    async fn fallback<State>(&self, req: &Request<State>, path: &str) -> Result<Option<Response>> {
+        let fallback = match &self.options.fallback {
+            Some(fallback) => fallback,
+            None => return Ok(None),
+        };
+        // Requests for missing assets, such as `app.js`, still fail.
+        let name = path.rsplit('/').next().unwrap_or_default();
+        if !is_navigation(req) || name.contains('.') {
+            return Ok(None);
+        }
+
+        log::info!("Serving fallback for: {:?}", path);
+        let mut res = match self.serve(req, fallback.trim_start_matches('/')).await? {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        res.append_header(headers::VARY, "Accept");
+        Ok(Some(res))
+    }
+
+    /// Open the file at `path`, or `None` if there is no such file.
+    async fn open(&self, path: &str) -> io::Result<Option<StaticFile>> {
+        match self.fs.open(path).await {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 }
 
+/// Respond with an open file, whose content type is `mime`.
+fn respond<State>(req: &Request<State>, file: StaticFile, mime: Mime) -> Response {
+    let len = file.metadata().len();
+    let modified = file.metadata().modified();
+    file_response(req, file, len, mime, modified)
+}
+
+/// The encodings of precompressed files the client accepts, with their file
+/// extensions, from the most preferred to the least. Brotli is preferred to
+/// gzip when the client has no preference.
+fn accepted_encodings<State>(req: &Request<State>) -> Vec<(Encoding, &'static str)> {
+    let accept = match AcceptEncoding::from_headers(req) {
+        Ok(Some(accept)) => accept,
+        _ => return Vec::new(),
+    };
+    let mut encodings: Vec<_> = [(Encoding::Brotli, "br"), (Encoding::Gzip, "gz")]
+        .iter()
+        .copied()
+        .filter_map(|(encoding, extension)| {
+            let weight = accept
+                .iter()
+                .find(|proposal| proposal.encoding() == &encoding)
+                .map(|proposal| proposal.weight().unwrap_or(1.0))
+                .or_else(|| accept.wildcard().then_some(1.0))?;
+            (weight > 0.0).then_some((weight, encoding, extension))
+        })
+        .collect();
+    // The sort is stable, so equal weights keep the server's order.
+    encodings.sort_by(|a, b| b.0.total_cmp(&a.0));
+    encodings
+        .into_iter()
+        .map(|(_, encoding, extension)| (encoding, extension))
+        .collect()
+}
+
 #[async_trait::async_trait]
-impl<State> Endpoint<State> for ServeDir
+impl<State, F> Endpoint<State> for ServeDir<F>
 where
     State: Clone + Send + Sync + 'static,
+    F: StaticFs,
 {
     async fn call(&self, req: Request<State>) -> Result {
-        let path = req.url().path();
-        let path = path
-            .strip_prefix(&self.prefix.trim_end_matches('*'))
-            .unwrap();
-        let path = path.trim_start_matches('/');
+        // The wildcard holds the part of the path below the directory, even
+        // when the route has params or the endpoint is nested, but leaves out
+        // the trailing slash.
+        let mut path = req.wildcard().unwrap_or_default().to_owned();
+        if !path.is_empty() && !path.ends_with('/') && req.url().path().ends_with('/') {
+            path.push('/');
+        }
+        let path = match percent_decode_str(path.trim_start_matches('/')).decode_utf8() {
+            Ok(path) => path,
+            Err(_) => return Ok(Response::new(StatusCode::NotFound)),
+        };
+        let path = &*path;
 
         log::info!("Requested file: {:?}", path);
 
-        let file = match self.dir.open(path).await {
-            Ok(file) => file,
-            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+        if !self.options.hidden && path.split('/').any(|segment| segment.starts_with('.')) {
+            log::warn!("Hidden path requested: {:?}", path);
+            return Ok(Response::new(StatusCode::NotFound));
+        }
+
+        if let Some(original) = self.manifest.original(path) {
+            if let Some(mut res) = self.serve(&req, original).await? {
+                res.insert_header(headers::CACHE_CONTROL, IMMUTABLE);
+                return Ok(res);
+            }
+        }
+
+        let metadata = match self.fs.metadata(path).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
                 log::warn!("Unauthorized attempt to read: {:?}", path);
                 return Ok(Response::new(StatusCode::Forbidden));
             }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(e) if is_not_found(&e) => {
+                if let Some(res) = self.fallback(&req, path).await? {
+                    return Ok(res);
+                }
                 log::warn!("File not found: {:?}", path);
                 return Ok(Response::new(StatusCode::NotFound));
             }
             Err(e) => return Err(e.into()),
         };
 
-        // TODO: This always uses `mime::BYTE_STREAM`; with http-types 3.0
-        // we'll be able to use `Body::from_open_file` which fixes this.
-        let body = Body::from_reader(BufReader::new(file), None);
-        Ok(Response::builder(StatusCode::Ok).body(body).build())
+        if !metadata.is_dir() {
+            let res = self.serve(&req, path).await?;
+            return Ok(res.unwrap_or_else(|| Response::new(StatusCode::NotFound)));
+        }
+
+        // Redirect to the slash form of the directory, relative to its last
+        // segment, so relative links in its index resolve inside it.
+        if !req.url().path().ends_with('/') {
+            let name = req.url().path().rsplit('/').next().unwrap_or_default();
+            let location = match req.url().query() {
+                Some(query) => format!("{}/?{}", name, query),
+                None => format!("{}/", name),
+            };
+            return Ok(Redirect::permanent(location).into());
+        }
+
+        for index in &self.options.index_files {
+            if let Some(res) = self.serve(&req, &format!("{}{}", path, index)).await? {
+                return Ok(res);
+            }
+        }
+        if self.options.listing {
+            return listing::respond(&req, &self.fs, path, self.options.hidden).await;
+        }
+        log::warn!("No index file in directory: {:?}", path);
+        Ok(Response::new(StatusCode::NotFound))
     }
 }
 
+/// Whether a request looks like a browser navigating to a page: a `GET` or
+/// `HEAD` request accepting HTML.
+fn is_navigation<State>(req: &Request<State>) -> bool {
+    if !matches!(req.method(), Method::Get | Method::Head) {
+        return false;
+    }
+    match Accept::from_headers(req) {
+        Ok(Some(accept)) => accept
+            .iter()
+            .any(|proposal| proposal.essence() == "text/html" && proposal.weight() != Some(0.0)),
+        _ => false,
+    }
+}
+
+/// Whether an error opening a path means there is nothing to serve there,
+/// including when a file is used as a directory.
+fn is_not_found(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -59,13 +257,10 @@ mod test {
     use cap_async_std::ambient_authority;
     use cap_async_std::fs::Dir;
 
-    fn serve_dir(tempdir: &tempfile::TempDir) -> crate::Result<ServeDir> {
+    fn serve_dir(tempdir: &tempfile::TempDir) -> crate::Result<ServeDir<Dir>> {
         let static_dir = async_std::task::block_on(async { setup_static_dir(tempdir).await })?;
 
-        Ok(ServeDir {
-            prefix: "/static/".to_string(),
-            dir: static_dir,
-        })
+        Ok(ServeDir::new(static_dir, ServeDirOptions::new()))
     }
 
     async fn setup_static_dir(tempdir: &tempfile::TempDir) -> crate::Result<Dir> {
@@ -82,7 +277,10 @@ mod test {
         let request = crate::http::Request::get(
             crate::http::Url::parse(&format!("http://localhost/{}", path)).unwrap(),
         );
-        crate::Request::new((), request, vec![])
+        // As routed by `/static/*`.
+        let mut captures = routefinder::Captures::new();
+        captures.set_wildcard(path.trim_start_matches("static/").to_owned());
+        crate::Request::new((), request, vec![captures])
     }
 
     #[async_std::test]
@@ -96,6 +294,8 @@ mod test {
         let mut res: crate::http::Response = res.into();
 
         assert_eq!(res.status(), 200);
+        assert!(res.header("Last-Modified").is_some());
+        assert!(res.header("ETag").is_some());
         assert_eq!(res.body_string().await.unwrap(), "Foobar");
     }
 
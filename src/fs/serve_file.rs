@@ -1,9 +1,13 @@
+use super::file_response;
+use crate::http::{mime, Mime};
 use crate::log;
-use crate::{Body, Endpoint, Request, Response, Result, StatusCode};
-use std::io;
+use crate::{Endpoint, Request, Response, Result, StatusCode};
+use std::io::{self, SeekFrom};
 use std::path::Path;
 
-use async_std::path::PathBuf as AsyncPathBuf;
+use async_std::fs::File;
+use async_std::path::{Path as AsyncPath, PathBuf as AsyncPathBuf};
+use async_std::prelude::*;
 use async_trait::async_trait;
 
 pub(crate) struct ServeFile {
@@ -22,18 +26,44 @@ impl ServeFile {
 
 #[async_trait]
 impl<State: Clone + Send + Sync + 'static> Endpoint<State> for ServeFile {
-    async fn call(&self, _: Request<State>) -> Result {
-        match Body::from_file(&self.path).await {
-            Ok(body) => Ok(Response::builder(StatusCode::Ok).body(body).build()),
+    async fn call(&self, req: Request<State>) -> Result {
+        let mut file = match File::open(&self.path).await {
+            Ok(file) => file,
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
                 log::warn!("File not found: {:?}", &self.path);
-                Ok(Response::new(StatusCode::NotFound))
+                return Ok(Response::new(StatusCode::NotFound));
             }
-            Err(e) => Err(e.into()),
-        }
+            Err(e) => return Err(e.into()),
+        };
+
+        let metadata = file.metadata().await?;
+        let mime = sniff_mime(&mut file, &self.path).await?;
+        Ok(file_response(
+            &req,
+            file,
+            metadata.len(),
+            mime,
+            metadata.modified().ok(),
+        ))
     }
 }
 
+/// Determine the mime type of a file like `Body::from_file` does: from its
+/// magic bytes, then from its extension, falling back to a byte stream.
+async fn sniff_mime(file: &mut File, path: &AsyncPath) -> io::Result<Mime> {
+    // The first 300 bytes are needed to infer formats such as tar.
+    let mut buf = [0; 300];
+    let n = file.read(&mut buf).await?;
+    file.seek(SeekFrom::Start(0)).await?;
+
+    let mime = Mime::sniff(&buf[..n]).ok().or_else(|| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Mime::from_extension)
+    });
+    Ok(mime.unwrap_or(mime::BYTE_STREAM))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -67,6 +97,8 @@ mod test {
         let mut res: Response = serve_file.call(request("static/foo")).await.unwrap().into();
 
         assert_eq!(res.status(), 200);
+        assert!(res.header("Last-Modified").is_some());
+        assert!(res.header("ETag").is_some());
         assert_eq!(res.body_string().await.unwrap(), "Foobar");
     }
 
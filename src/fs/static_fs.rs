@@ -0,0 +1,284 @@
+use async_std::io::{self, Read, Seek, SeekFrom};
+use async_std::task;
+use cap_async_std::ambient_authority;
+use cap_async_std::fs::Dir;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{self, Debug};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+
+/// A read-only filesystem static files are served from.
+///
+/// Paths are relative to the root of the filesystem and use `/` as a
+/// separator. The root itself is the empty path, and paths of directories
+/// may end with a `/`. Errors of kind `NotFound` are answered with
+/// `404 Not Found`, and errors of kind `PermissionDenied` with
+/// `403 Forbidden`.
+///
+/// Tide implements this trait for [`cap_async_std::fs::Dir`], which is what
+/// [`Route::serve_dir`](crate::Route::serve_dir) opens when given a path, for
+/// [`MemoryFs`](super::MemoryFs) and for [`TarFs`](super::TarFs).
+#[async_trait::async_trait]
+pub trait StaticFs: Send + Sync + 'static {
+    /// Get the metadata of the file or directory at `path`.
+    async fn metadata(&self, path: &str) -> io::Result<Metadata>;
+
+    /// Open the file at `path` for reading. Opening a directory fails with
+    /// an error of kind `NotFound`.
+    async fn open(&self, path: &str) -> io::Result<StaticFile>;
+
+    /// List the entries of the directory at `path`, in any order.
+    async fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>>;
+}
+
+/// Metadata about a file or directory of a [`StaticFs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    is_dir: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Metadata {
+    /// Create the metadata of a file of `len` bytes.
+    #[must_use]
+    pub fn file(len: u64, modified: Option<SystemTime>) -> Self {
+        Self {
+            is_dir: false,
+            len,
+            modified,
+        }
+    }
+
+    /// Create the metadata of a directory.
+    #[must_use]
+    pub fn dir(modified: Option<SystemTime>) -> Self {
+        Self {
+            is_dir: true,
+            len: 0,
+            modified,
+        }
+    }
+
+    /// Whether this is the metadata of a directory.
+    #[must_use]
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// The length of the file in bytes, or `0` for a directory.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// The last modification time, if it is known.
+    ///
+    /// Files without one are served without `Last-Modified` and `ETag`
+    /// headers.
+    #[must_use]
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+/// An entry of a directory of a [`StaticFs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    name: String,
+    metadata: Metadata,
+}
+
+impl DirEntry {
+    /// Create a directory entry.
+    #[must_use]
+    pub fn new(name: impl Into<String>, metadata: Metadata) -> Self {
+        Self {
+            name: name.into(),
+            metadata,
+        }
+    }
+
+    /// The name of the entry, without the path of its directory.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The metadata of the entry.
+    #[must_use]
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+trait ReadSeek: Read + Seek + Send + Sync {}
+
+impl<T: Read + Seek + Send + Sync> ReadSeek for T {}
+
+/// A file opened from a [`StaticFs`].
+pub struct StaticFile {
+    reader: Pin<Box<dyn ReadSeek>>,
+    metadata: Metadata,
+}
+
+impl StaticFile {
+    /// Create a file read from `reader`, with the given metadata.
+    pub fn new<R>(reader: R, metadata: Metadata) -> Self
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        Self {
+            reader: Box::pin(reader),
+            metadata,
+        }
+    }
+
+    /// The metadata of the file.
+    #[must_use]
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+impl Debug for StaticFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticFile")
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+impl Read for StaticFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.reader.as_mut().poll_read(cx, buf)
+    }
+}
+
+impl Seek for StaticFile {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        self.reader.as_mut().poll_seek(cx, pos)
+    }
+}
+
+/// Conversion into a [`StaticFs`], for
+/// [`Route::serve_dir`](crate::Route::serve_dir).
+///
+/// Paths are opened as a [`cap_async_std::fs::Dir`], and every [`StaticFs`]
+/// is used as is. Code generic over `P: AsRef<Path>` can pass
+/// `path.as_ref()`.
+pub trait IntoStaticFs {
+    /// The filesystem this converts into.
+    type Fs: StaticFs;
+
+    /// Convert into a [`StaticFs`].
+    fn into_static_fs(self) -> io::Result<Self::Fs>;
+}
+
+impl<F: StaticFs> IntoStaticFs for F {
+    type Fs = F;
+
+    fn into_static_fs(self) -> io::Result<Self::Fs> {
+        Ok(self)
+    }
+}
+
+/// Open a directory, verifying it exists.
+fn open_dir(path: &Path) -> io::Result<Dir> {
+    let path = path.canonicalize()?;
+    task::block_on(async { Dir::open_ambient_dir(path, ambient_authority()).await })
+}
+
+macro_rules! impl_into_static_fs_for_path {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoStaticFs for $ty {
+                type Fs = Dir;
+
+                fn into_static_fs(self) -> io::Result<Self::Fs> {
+                    open_dir(self.as_ref())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_static_fs_for_path!(
+    &str,
+    String,
+    &String,
+    &Path,
+    PathBuf,
+    &PathBuf,
+    &OsStr,
+    OsString,
+    &OsString,
+    Cow<'_, Path>,
+    Cow<'_, OsStr>
+);
+
+#[async_trait::async_trait]
+impl StaticFs for Dir {
+    async fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        let metadata = Dir::metadata(self, dir_path(path)).await?;
+        Ok(metadata_from_cap(&metadata))
+    }
+
+    async fn open(&self, path: &str) -> io::Result<StaticFile> {
+        // Opening special files such as FIFOs could block.
+        if !Dir::metadata(self, path).await?.is_file() {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        let file = Dir::open(self, path).await?;
+        let metadata = metadata_from_cap(&file.metadata()?);
+        Ok(StaticFile::new(file, metadata))
+    }
+
+    async fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        let path = dir_path(path);
+        let mut entries = Vec::new();
+        for entry in Dir::read_dir(self, path).await? {
+            let name = match entry?.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            // Follow symlinks, leaving out the ones leading out of the
+            // directory.
+            let metadata = match Dir::metadata(self, format!("{}/{}", path, name)).await {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            entries.push(DirEntry::new(name, metadata_from_cap(&metadata)));
+        }
+        Ok(entries)
+    }
+}
+
+/// The path of a directory of a `Dir`, which can't be empty.
+fn dir_path(path: &str) -> &str {
+    if path.is_empty() {
+        "."
+    } else {
+        path
+    }
+}
+
+fn metadata_from_cap(metadata: &cap_async_std::fs::Metadata) -> Metadata {
+    let modified = metadata.modified().ok().map(|modified| modified.into_std());
+    if metadata.is_dir() {
+        Metadata::dir(modified)
+    } else {
+        Metadata::file(metadata.len(), modified)
+    }
+}
@@ -0,0 +1,377 @@
+use super::memory::Tree;
+use super::{DirEntry, Metadata, StaticFile, StaticFs};
+
+use async_std::io;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The size of tar headers, and the alignment of file contents.
+const BLOCK: usize = 512;
+
+/// A read-only filesystem with the files of a tar archive, kept in memory.
+///
+/// Regular files and directories are read from ustar, GNU and pax archives;
+/// other entries, such as links, are left out. Files keep the modification
+/// time recorded in the archive.
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
+/// #
+/// use tide::fs::TarFs;
+///
+/// let assets = TarFs::open("assets.tar").await?;
+///
+/// let mut app = tide::new();
+/// app.at("/assets/*").serve_dir(assets)?;
+/// app.listen("localhost:8080").await?;
+/// # Ok(()) }) }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TarFs {
+    tree: Tree,
+}
+
+impl TarFs {
+    /// Create a new instance of `TarFs` from the bytes of a tar archive,
+    /// such as an archive embedded in the binary with `include_bytes!`.
+    ///
+    /// # Errors
+    ///
+    /// An error of kind `InvalidData` is returned if the archive is
+    /// malformed.
+    pub fn new(archive: impl Into<Cow<'static, [u8]>>) -> io::Result<Self> {
+        let tree = parse(Arc::new(archive.into()))?;
+        Ok(Self { tree })
+    }
+
+    /// Read the tar archive at `path` into a new instance of `TarFs`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the file can't be read, or is not a well
+    /// formed tar archive.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let archive = async_std::fs::read(path.as_ref()).await?;
+        Self::new(archive)
+    }
+}
+
+#[async_trait::async_trait]
+impl StaticFs for TarFs {
+    async fn metadata(&self, path: &str) -> io::Result<Metadata> {
+        self.tree.metadata(path)
+    }
+
+    async fn open(&self, path: &str) -> io::Result<StaticFile> {
+        self.tree.open(path)
+    }
+
+    async fn read_dir(&self, path: &str) -> io::Result<Vec<DirEntry>> {
+        self.tree.read_dir(path)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Index the files and directories of a tar archive.
+fn parse(archive: Arc<Cow<'static, [u8]>>) -> io::Result<Tree> {
+    let mut tree = Tree::default();
+    tree.insert_dir("", None);
+
+    // The path and modification time given by GNU long name and pax
+    // extended headers, for the next entry.
+    let mut next_path = None;
+    let mut next_modified = None;
+
+    let mut offset = 0;
+    while offset + BLOCK <= archive.len() {
+        let header = &archive[offset..offset + BLOCK];
+        // The archive ends with empty blocks.
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        verify_checksum(header)?;
+
+        let size = usize::try_from(number(&header[124..136])?)
+            .map_err(|_| invalid("tar entry too large"))?;
+        let start = offset + BLOCK;
+        let end = start
+            .checked_add(size)
+            .filter(|end| *end <= archive.len())
+            .ok_or_else(|| invalid("truncated tar entry"))?;
+        offset = start + size.div_ceil(BLOCK) * BLOCK;
+
+        let data = &archive[start..end];
+        let kind = header[156];
+        match kind {
+            b'L' => {
+                next_path = text(data).map(str::to_owned);
+                continue;
+            }
+            b'x' => {
+                for (key, value) in pax_records(data)? {
+                    match key {
+                        "path" => next_path = Some(value.to_owned()),
+                        "mtime" => next_modified = pax_time(value)?,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let path = match next_path.take() {
+            Some(path) => Some(path),
+            None => ustar_path(header),
+        };
+        let modified = match next_modified.take() {
+            Some(modified) => modified,
+            None => time(Duration::from_secs(number(&header[136..148])?))?,
+        };
+        let path = match path {
+            Some(path) => path,
+            None => continue,
+        };
+        match kind {
+            b'0' | b'\0' | b'7' => {
+                tree.insert_file(&path, archive.clone(), start..end, Some(modified))
+            }
+            b'5' => tree.insert_dir(&path, Some(modified)),
+            _ => {}
+        }
+    }
+    Ok(tree)
+}
+
+/// Check the checksum of a header, which is computed with the checksum field
+/// itself filled with spaces.
+fn verify_checksum(header: &[u8]) -> io::Result<()> {
+    let expected = number(&header[148..156])?;
+    let sum: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| {
+            if (148..156).contains(&i) {
+                u64::from(b' ')
+            } else {
+                u64::from(*byte)
+            }
+        })
+        .sum();
+    if sum == expected {
+        Ok(())
+    } else {
+        Err(invalid("invalid tar header checksum"))
+    }
+}
+
+/// Parse a numeric header field: octal digits padded with spaces or NULs, or
+/// a big-endian binary number if the high bit of its first byte is set.
+fn number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut n = u64::from(field[0] & 0x7f);
+        for byte in &field[1..] {
+            n = n
+                .checked_mul(256)
+                .and_then(|n| n.checked_add(u64::from(*byte)))
+                .ok_or_else(|| invalid("tar number too large"))?;
+        }
+        return Ok(n);
+    }
+    let digits = text(field)
+        .map(|text| text.trim_matches(' '))
+        .ok_or_else(|| invalid("invalid tar number"))?;
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(digits, 8).map_err(|_| invalid("invalid tar number"))
+}
+
+/// The text of a field, up to its first NUL, if it is UTF-8.
+fn text(field: &[u8]) -> Option<&str> {
+    let len = field
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(field.len());
+    std::str::from_utf8(&field[..len]).ok()
+}
+
+/// The path of a ustar header, joined with its prefix if it has one.
+fn ustar_path(header: &[u8]) -> Option<String> {
+    let name = text(&header[0..100])?;
+    let prefix = if &header[257..262] == b"ustar" {
+        text(&header[345..500])?
+    } else {
+        ""
+    };
+    if prefix.is_empty() {
+        Some(name.to_owned())
+    } else {
+        Some(format!("{}/{}", prefix, name))
+    }
+}
+
+/// Parse the `<length> <key>=<value>\n` records of a pax extended header.
+fn pax_records(mut data: &[u8]) -> io::Result<Vec<(&str, &str)>> {
+    let mut records = Vec::new();
+    while !data.is_empty() && data[0] != 0 {
+        let space = data
+            .iter()
+            .position(|byte| *byte == b' ')
+            .ok_or_else(|| invalid("invalid pax record"))?;
+        let len = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|len| *len > space && *len <= data.len())
+            .ok_or_else(|| invalid("invalid pax record"))?;
+        let record = std::str::from_utf8(&data[space + 1..len])
+            .map_err(|_| invalid("invalid pax record"))?;
+        let record = record.strip_suffix('\n').unwrap_or(record);
+        if let Some((key, value)) = record.split_once('=') {
+            records.push((key, value));
+        }
+        data = &data[len..];
+    }
+    Ok(records)
+}
+
+/// Parse a pax time, in seconds since the epoch with an optional fraction.
+/// Times that can't be parsed are ignored.
+fn pax_time(value: &str) -> io::Result<Option<SystemTime>> {
+    let (secs, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let (secs, nanos) = match (secs.parse(), format!("{:0<9.9}", fraction).parse()) {
+        (Ok(secs), Ok(nanos)) => (secs, nanos),
+        _ => return Ok(None),
+    };
+    time(Duration::new(secs, nanos)).map(Some)
+}
+
+/// The time a duration after the epoch, if the system can represent it.
+fn time(since_epoch: Duration) -> io::Result<SystemTime> {
+    UNIX_EPOCH
+        .checked_add(since_epoch)
+        .ok_or_else(|| invalid("tar time out of range"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Build a tar header for `name`, with the given type and contents.
+    fn header(name: &[u8], kind: u8, contents: &[u8], mtime: u64) -> Vec<u8> {
+        let mut header = vec![0; BLOCK];
+        header[..name.len()].copy_from_slice(name);
+        header[100..108].copy_from_slice(b"0000644\0");
+        let size = format!("{:011o}\0", contents.len());
+        header[124..136].copy_from_slice(size.as_bytes());
+        let mtime = format!("{:011o}\0", mtime);
+        header[136..148].copy_from_slice(mtime.as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        checksum(&mut header);
+
+        header.extend_from_slice(contents);
+        header.resize(header.len().div_ceil(BLOCK) * BLOCK, 0);
+        header
+    }
+
+    /// Fill in the checksum of a tar header.
+    fn checksum(header: &mut [u8]) {
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header[..BLOCK].iter().map(|byte| u32::from(*byte)).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    }
+
+    #[async_std::test]
+    async fn reads_archives() -> io::Result<()> {
+        let long_name = format!("{}/long.txt", "d".repeat(120));
+        let pax = b"22 mtime=1000000000.5\n16 path=pax.txt\n";
+        let archive = [
+            header(b"./dir/", b'5', b"", 100),
+            header(b"./dir/a.txt", b'0', b"alpha", 200),
+            header(b"././@LongLink", b'L', long_name.as_bytes(), 0),
+            header(b"ignored", b'0', b"long", 300),
+            header(b"PaxHeader", b'x', pax, 0),
+            header(b"ignored", b'0', b"pax", 400),
+            header(b"link", b'2', b"", 500),
+            vec![0; BLOCK * 2],
+        ]
+        .concat();
+
+        let fs = TarFs::new(archive)?;
+        let metadata = fs.metadata("dir/a.txt").await?;
+        assert_eq!(metadata.len(), 5);
+        assert_eq!(
+            metadata.modified(),
+            Some(UNIX_EPOCH + Duration::from_secs(200))
+        );
+        assert_eq!(fs.metadata(&long_name).await?.len(), 4);
+        let metadata = fs.metadata("pax.txt").await?;
+        assert_eq!(
+            metadata.modified(),
+            Some(UNIX_EPOCH + Duration::new(1_000_000_000, 500_000_000))
+        );
+        assert!(fs.metadata("dir").await?.is_dir());
+        assert!(fs.metadata("link").await.is_err());
+        assert!(fs.metadata("ignored").await.is_err());
+
+        let names: Vec<_> = fs
+            .read_dir("")
+            .await?
+            .into_iter()
+            .map(|e| e.name().to_owned())
+            .collect();
+        assert_eq!(
+            names,
+            ["d".repeat(120), "dir".to_owned(), "pax.txt".to_owned()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_malformed_archives() {
+        let mut archive = header(b"a.txt", b'0', b"alpha", 0);
+        archive[0] = b'b';
+        assert_eq!(
+            TarFs::new(archive).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut archive = header(b"a.txt", b'0', b"alpha", 0);
+        archive.truncate(BLOCK + 2);
+        assert_eq!(
+            TarFs::new(archive).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let pax = b"30 mtime=18446744073709551615\n";
+        let archive = [
+            header(b"PaxHeader", b'x', pax, 0),
+            header(b"a.txt", b'0', b"alpha", 0),
+        ]
+        .concat();
+        assert_eq!(
+            TarFs::new(archive).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // A base-256 mtime of `u64::MAX` seconds.
+        let mut archive = header(b"a.txt", b'0', b"alpha", 0);
+        archive[136..140].copy_from_slice(&[0x80, 0, 0, 0]);
+        archive[140..148].copy_from_slice(&[0xff; 8]);
+        checksum(&mut archive);
+        assert_eq!(
+            TarFs::new(archive).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -68,7 +68,6 @@
 #[cfg(feature = "cookies")]
 mod cookies;
 mod endpoint;
-mod fs;
 mod middleware;
 mod redirect;
 mod request;
@@ -78,9 +77,15 @@ mod route;
 mod router;
 mod server;
 
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod conditional;
 pub mod convert;
+pub mod extract;
+pub mod fs;
 pub mod listener;
 pub mod log;
+pub mod multipart;
 pub mod prelude;
 pub mod security;
 pub mod utils;
@@ -96,7 +101,7 @@ pub use redirect::Redirect;
 pub use request::Request;
 pub use response::Response;
 pub use response_builder::ResponseBuilder;
-pub use route::Route;
+pub use route::{Route, RouteError, RouteInfo};
 pub use server::Server;
 
 pub use http_types::{self as http, Body, Error, Status, StatusCode};
@@ -4,8 +4,11 @@ mod concurrent_listener;
 mod failover_listener;
 #[cfg(feature = "h1-server")]
 mod parsed_listener;
+mod shutdown;
 #[cfg(feature = "h1-server")]
 mod tcp_listener;
+#[cfg(feature = "tls")]
+mod tls_listener;
 mod to_listener;
 #[cfg(feature = "h1-server")]
 mod to_listener_impls;
@@ -21,8 +24,13 @@ use crate::Server;
 
 pub use concurrent_listener::ConcurrentListener;
 pub use failover_listener::FailoverListener;
+pub use shutdown::Shutdown;
 pub use to_listener::ToListener;
 
+#[cfg(feature = "tls")]
+#[cfg_attr(feature = "docs", doc(cfg(feature = "tls")))]
+pub use tls_listener::TlsListener;
+
 #[cfg(feature = "h1-server")]
 pub(crate) use parsed_listener::ParsedListener;
 #[cfg(feature = "h1-server")]
@@ -45,7 +53,9 @@ where
     async fn bind(&mut self, app: Server<State>) -> io::Result<()>;
 
     /// Start accepting incoming connections. This method must be called only
-    /// after `bind` has succeeded.
+    /// after `bind` has succeeded. It returns once the server's
+    /// [`Shutdown`] handle has been triggered and in-flight requests have
+    /// drained.
     async fn accept(&mut self) -> io::Result<()>;
 
     /// Expose information about the connection. This should always return valid
@@ -85,6 +95,66 @@ pub(crate) fn is_transient_error(e: &io::Error) -> bool {
     )
 }
 
+/// crate-internal shared logic used by tcp, tls and unix listeners to serve a
+/// single connection. Requests received over `tls` report an `https` url.
+/// Once the server's [`Shutdown`] handle is triggered, idle keep-alive
+/// connections are closed, while a request that is already being handled is
+/// allowed to complete before the connection is closed.
+#[cfg(feature = "h1-server")]
+pub(crate) async fn serve_connection<State, RW>(
+    app: Server<State>,
+    stream: RW,
+    local_addr: Option<String>,
+    peer_addr: Option<String>,
+    tls: bool,
+) -> http_types::Result<()>
+where
+    State: Clone + Send + Sync + 'static,
+    RW: io::Read + io::Write + Clone + Send + Sync + Unpin + 'static,
+{
+    use async_h1::server::{ConnectionStatus, Server as H1Server};
+    use async_std::future;
+    use async_std::prelude::FutureExt;
+    use http_types::headers::CONNECTION;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let shutdown = app.shutdown_handle();
+    let busy = AtomicBool::new(false);
+
+    let mut server = H1Server::new(stream, |mut req| async {
+        busy.store(true, Ordering::SeqCst);
+        req.set_local_addr(local_addr.as_ref());
+        req.set_peer_addr(peer_addr.as_ref());
+        if tls {
+            req.url_mut().set_scheme("https").ok();
+        }
+        let mut res: http_types::Response = app.respond(req).await?;
+        if shutdown.is_triggered() {
+            res.insert_header(CONNECTION, "close");
+        }
+        Ok(res)
+    });
+
+    loop {
+        busy.store(false, Ordering::SeqCst);
+        let status = server
+            .accept_one()
+            .race(async {
+                shutdown.wait().await;
+                if busy.load(Ordering::SeqCst) {
+                    future::pending().await
+                } else {
+                    Ok(ConnectionStatus::Close)
+                }
+            })
+            .await?;
+
+        if status == ConnectionStatus::Close || shutdown.is_triggered() {
+            return Ok(());
+        }
+    }
+}
+
 /// Information about the `Listener`.
 ///
 /// See [`Report`](../listener/trait.Report.html) for more.
@@ -1,3 +1,5 @@
+#[cfg(feature = "tls")]
+use super::TlsListener;
 #[cfg(unix)]
 use super::UnixListener;
 use super::{ListenInfo, Listener, TcpListener};
@@ -17,6 +19,8 @@ pub enum ParsedListener<State> {
     #[cfg(unix)]
     Unix(UnixListener<State>),
     Tcp(TcpListener<State>),
+    #[cfg(feature = "tls")]
+    Tls(TlsListener<State>),
 }
 
 impl<State> Debug for ParsedListener<State> {
@@ -25,6 +29,8 @@ impl<State> Debug for ParsedListener<State> {
             #[cfg(unix)]
             ParsedListener::Unix(unix) => Debug::fmt(unix, f),
             ParsedListener::Tcp(tcp) => Debug::fmt(tcp, f),
+            #[cfg(feature = "tls")]
+            ParsedListener::Tls(tls) => Debug::fmt(tls, f),
         }
     }
 }
@@ -35,6 +41,8 @@ impl<State> Display for ParsedListener<State> {
             #[cfg(unix)]
             Self::Unix(u) => write!(f, "{}", u),
             Self::Tcp(t) => write!(f, "{}", t),
+            #[cfg(feature = "tls")]
+            Self::Tls(t) => write!(f, "{}", t),
         }
     }
 }
@@ -49,6 +57,8 @@ where
             #[cfg(unix)]
             Self::Unix(u) => u.bind(server).await,
             Self::Tcp(t) => t.bind(server).await,
+            #[cfg(feature = "tls")]
+            Self::Tls(t) => t.bind(server).await,
         }
     }
 
@@ -57,6 +67,8 @@ where
             #[cfg(unix)]
             Self::Unix(u) => u.accept().await,
             Self::Tcp(t) => t.accept().await,
+            #[cfg(feature = "tls")]
+            Self::Tls(t) => t.accept().await,
         }
     }
 
@@ -65,6 +77,8 @@ where
             #[cfg(unix)]
             ParsedListener::Unix(unix) => unix.info(),
             ParsedListener::Tcp(tcp) => tcp.info(),
+            #[cfg(feature = "tls")]
+            ParsedListener::Tls(tls) => tls.info(),
         }
     }
 }
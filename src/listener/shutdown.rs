@@ -0,0 +1,168 @@
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "h1-server")]
+use async_std::future;
+use event_listener::Event;
+
+/// A handle used to gracefully shut down a running server.
+///
+/// Every [`Server`](crate::Server) owns a `Shutdown` handle, which can be
+/// obtained through [`Server::shutdown_handle`](crate::Server::shutdown_handle)
+/// before the server is bound. Triggering the handle makes every listener the
+/// server was bound to (including the listeners inside a
+/// [`ConcurrentListener`](crate::listener::ConcurrentListener) or
+/// [`FailoverListener`](crate::listener::FailoverListener)) stop accepting new
+/// connections. `Listener::accept` then waits for in-flight requests to
+/// finish, for at most the grace period passed to [`Shutdown::trigger`],
+/// before returning.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use async_std::task;
+/// # fn main() -> Result<(), std::io::Error> { task::block_on(async {
+/// #
+/// use std::time::Duration;
+///
+/// let mut app = tide::new();
+/// app.at("/").get(|_| async { Ok("Hello, world!") });
+///
+/// let shutdown = app.shutdown_handle();
+/// task::spawn(async move {
+///     task::sleep(Duration::from_secs(60)).await;
+///     shutdown.trigger(Duration::from_secs(10));
+/// });
+///
+/// // Returns once the handle was triggered and all requests have drained.
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    triggered: AtomicBool,
+    grace_period: Mutex<Option<Duration>>,
+    stop: Event,
+    in_flight: AtomicUsize,
+    #[cfg(feature = "h1-server")]
+    drained: Event,
+}
+
+impl Shutdown {
+    /// Create a new, untriggered shutdown handle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop accepting new connections and wait up to `grace_period` for
+    /// in-flight requests to complete.
+    ///
+    /// Calling this more than once has no further effect.
+    pub fn trigger(&self, grace_period: Duration) {
+        let mut slot = self.inner.grace_period.lock().unwrap();
+        if self.is_triggered() {
+            return;
+        }
+        *slot = Some(grace_period);
+        self.inner.triggered.store(true, Ordering::SeqCst);
+        drop(slot);
+        self.inner.stop.notify(usize::MAX);
+    }
+
+    /// Has this handle been triggered?
+    pub fn is_triggered(&self) -> bool {
+        self.inner.triggered.load(Ordering::SeqCst)
+    }
+
+    /// The number of connections that are currently being served.
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the handle has been triggered.
+    #[cfg(feature = "h1-server")]
+    pub(crate) async fn wait(&self) {
+        loop {
+            if self.is_triggered() {
+                return;
+            }
+            let listener = self.inner.stop.listen();
+            if self.is_triggered() {
+                return;
+            }
+            listener.await;
+        }
+    }
+
+    /// Register a connection as in-flight until the returned guard is dropped.
+    #[cfg(feature = "h1-server")]
+    pub(crate) fn connection(&self) -> ConnectionGuard {
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        ConnectionGuard {
+            shutdown: self.clone(),
+        }
+    }
+
+    /// Wait for all in-flight connections to finish, bounded by the grace
+    /// period given to `trigger`.
+    #[cfg(feature = "h1-server")]
+    pub(crate) async fn drain(&self) {
+        let idle = async {
+            loop {
+                if self.in_flight() == 0 {
+                    return;
+                }
+                let listener = self.inner.drained.listen();
+                if self.in_flight() == 0 {
+                    return;
+                }
+                listener.await;
+            }
+        };
+
+        let grace_period = *self.inner.grace_period.lock().unwrap();
+        match grace_period {
+            Some(grace_period) => {
+                if future::timeout(grace_period, idle).await.is_err() {
+                    crate::log::warn!("Grace period elapsed, abandoning connections", {
+                        in_flight: self.in_flight()
+                    });
+                }
+            }
+            None => idle.await,
+        }
+    }
+}
+
+impl Debug for Shutdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("triggered", &self.is_triggered())
+            .field("in_flight", &self.in_flight())
+            .finish()
+    }
+}
+
+/// Marks a connection as in-flight for as long as it is alive.
+#[cfg(feature = "h1-server")]
+pub(crate) struct ConnectionGuard {
+    shutdown: Shutdown,
+}
+
+#[cfg(feature = "h1-server")]
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let inner = &self.shutdown.inner;
+        if inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            inner.drained.notify(usize::MAX);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::{is_transient_error, ListenInfo};
+use super::{is_transient_error, serve_connection, ListenInfo};
 
 use crate::listener::Listener;
 use crate::{log, Server};
@@ -45,15 +45,13 @@ impl<State> TcpListener<State> {
 }
 
 fn handle_tcp<State: Clone + Send + Sync + 'static>(app: Server<State>, stream: TcpStream) {
+    let guard = app.shutdown_handle().connection();
     task::spawn(async move {
-        let local_addr = stream.local_addr().ok();
-        let peer_addr = stream.peer_addr().ok();
+        let _guard = guard;
+        let local_addr = stream.local_addr().ok().map(|addr| addr.to_string());
+        let peer_addr = stream.peer_addr().ok().map(|addr| addr.to_string());
 
-        let fut = async_h1::accept(stream, |mut req| async {
-            req.set_local_addr(local_addr);
-            req.set_peer_addr(peer_addr);
-            app.respond(req).await
-        });
+        let fut = serve_connection(app, stream, local_addr, peer_addr, false);
 
         if let Err(error) = fut.await {
             log::error!("async-h1 error", { error: error.to_string() });
@@ -98,9 +96,16 @@ where
             .take()
             .expect("`Listener::bind` must be called before `Listener::accept`");
 
+        let shutdown = server.shutdown_handle();
         let mut incoming = listener.incoming();
 
-        while let Some(stream) = incoming.next().await {
+        let stop = async {
+            shutdown.wait().await;
+            None
+        };
+        futures_util::pin_mut!(stop);
+
+        while let Some(stream) = incoming.next().race(&mut stop).await {
             match stream {
                 Err(ref e) if is_transient_error(e) => continue,
                 Err(error) => {
@@ -115,6 +120,8 @@ where
                 }
             };
         }
+
+        shutdown.drain().await;
         Ok(())
     }
 
@@ -0,0 +1,307 @@
+use super::{is_transient_error, serve_connection, ListenInfo};
+
+use crate::listener::Listener;
+use crate::{log, Server};
+
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_std::future;
+use async_std::net::{self, SocketAddr, TcpStream};
+use async_std::prelude::*;
+use async_std::{fs, io, task};
+use futures_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use futures_rustls::{server, TlsAcceptor};
+
+/// This represents a tide [Listener](crate::listener::Listener) that
+/// wraps an [async_std::net::TcpListener] and terminates TLS on every
+/// accepted connection using [rustls](https://docs.rs/rustls).
+///
+/// The certificate chain and private key are read from PEM files when the
+/// listener is bound. Requests received through this listener report
+/// `https` from `req.url().scheme()`, and the listener's
+/// [`ListenInfo`](crate::listener::ListenInfo) is marked as encrypted.
+///
+/// A `TlsListener` is usually created from a listen string, passing the
+/// PEM files as query parameters:
+///
+/// ```no_run
+/// # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
+/// #
+/// let mut app = tide::new();
+/// app.at("/").get(|_| async { Ok("Hello, encrypted world!") });
+/// app.listen("https://localhost:4433?cert=cert.pem&key=key.pem").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+pub struct TlsListener<State> {
+    addrs: Option<Vec<SocketAddr>>,
+    listener: Option<net::TcpListener>,
+    cert: PathBuf,
+    key: PathBuf,
+    acceptor: Option<TlsAcceptor>,
+    server: Option<Server<State>>,
+    info: Option<ListenInfo>,
+}
+
+impl<State> TlsListener<State> {
+    /// Create a `TlsListener` that binds to `addrs`, presenting the PEM
+    /// encoded certificate chain at `cert` and private key at `key`.
+    pub fn from_addrs(
+        addrs: Vec<SocketAddr>,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            addrs: Some(addrs),
+            listener: None,
+            cert: cert.into(),
+            key: key.into(),
+            acceptor: None,
+            server: None,
+            info: None,
+        }
+    }
+
+    /// Create a `TlsListener` from an already bound tcp listener, presenting
+    /// the PEM encoded certificate chain at `cert` and private key at `key`.
+    pub fn from_listener(
+        tcp_listener: impl Into<net::TcpListener>,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            addrs: None,
+            listener: Some(tcp_listener.into()),
+            cert: cert.into(),
+            key: key.into(),
+            acceptor: None,
+            server: None,
+            info: None,
+        }
+    }
+}
+
+/// How long a client has to complete the TLS handshake, so connections that
+/// never finish it don't hold a task, or stall a graceful shutdown, forever.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Read the certificate chain and private key into a rustls configuration.
+async fn load_config(cert_path: &Path, key_path: &Path) -> io::Result<ServerConfig> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let certs = fs::read(cert_path).await?;
+    let certs = rustls_pemfile::certs(&mut certs.as_slice())?;
+    if certs.is_empty() {
+        return Err(invalid(format!("no certificates found in {:?}", cert_path)));
+    }
+    let certs = certs.into_iter().map(Certificate).collect();
+
+    let keys = fs::read(key_path).await?;
+    let key = rustls_pemfile::read_all(&mut keys.as_slice())?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| invalid(format!("no private key found in {:?}", key_path)))?;
+
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+fn handle_tls<State: Clone + Send + Sync + 'static>(
+    app: Server<State>,
+    acceptor: TlsAcceptor,
+    stream: TcpStream,
+) {
+    let guard = app.shutdown_handle().connection();
+    task::spawn(async move {
+        let _guard = guard;
+        let local_addr = stream.local_addr().ok().map(|addr| addr.to_string());
+        let peer_addr = stream.peer_addr().ok().map(|addr| addr.to_string());
+
+        let stream = match future::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+            Ok(Ok(stream)) => TlsStream(Arc::new(Mutex::new(stream))),
+            Ok(Err(error)) => {
+                log::error!("tls handshake error", { error: error.to_string() });
+                return;
+            }
+            Err(_) => {
+                log::error!("tls handshake timed out", { peer_addr: peer_addr });
+                return;
+            }
+        };
+
+        let fut = serve_connection(app, stream, local_addr, peer_addr, true);
+
+        if let Err(error) = fut.await {
+            log::error!("async-h1 error", { error: error.to_string() });
+        }
+    });
+}
+
+#[async_trait::async_trait]
+impl<State> Listener<State> for TlsListener<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn bind(&mut self, server: Server<State>) -> io::Result<()> {
+        assert!(self.server.is_none(), "`bind` should only be called once");
+        self.server = Some(server);
+
+        let config = load_config(&self.cert, &self.key).await?;
+        self.acceptor = Some(TlsAcceptor::from(Arc::new(config)));
+
+        if self.listener.is_none() {
+            let addrs = self
+                .addrs
+                .take()
+                .expect("`bind` should only be called once");
+            let listener = net::TcpListener::bind(addrs.as_slice()).await?;
+            self.listener = Some(listener);
+        }
+
+        // Format the listen information.
+        let conn_string = format!("{}", self);
+        let transport = "tcp".to_owned();
+        let tls = true;
+        self.info = Some(ListenInfo::new(conn_string, transport, tls));
+
+        Ok(())
+    }
+
+    async fn accept(&mut self) -> io::Result<()> {
+        let server = self
+            .server
+            .take()
+            .expect("`Listener::bind` must be called before `Listener::accept`");
+        let listener = self
+            .listener
+            .take()
+            .expect("`Listener::bind` must be called before `Listener::accept`");
+        let acceptor = self
+            .acceptor
+            .take()
+            .expect("`Listener::bind` must be called before `Listener::accept`");
+
+        let shutdown = server.shutdown_handle();
+        let mut incoming = listener.incoming();
+
+        let stop = async {
+            shutdown.wait().await;
+            None
+        };
+        futures_util::pin_mut!(stop);
+
+        while let Some(stream) = incoming.next().race(&mut stop).await {
+            match stream {
+                Err(ref e) if is_transient_error(e) => continue,
+                Err(error) => {
+                    let delay = Duration::from_millis(500);
+                    crate::log::error!("Error: {}. Pausing for {:?}.", error, delay);
+                    task::sleep(delay).await;
+                    continue;
+                }
+
+                Ok(stream) => {
+                    handle_tls(server.clone(), acceptor.clone(), stream);
+                }
+            };
+        }
+
+        shutdown.drain().await;
+        Ok(())
+    }
+
+    fn info(&self) -> Vec<ListenInfo> {
+        match &self.info {
+            Some(info) => vec![info.clone()],
+            None => vec![],
+        }
+    }
+}
+
+impl<State> fmt::Debug for TlsListener<State> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsListener")
+            .field("listener", &self.listener)
+            .field("addrs", &self.addrs)
+            .field("cert", &self.cert)
+            .field("key", &self.key)
+            .field(
+                "server",
+                if self.server.is_some() {
+                    &"Some(Server<State>)"
+                } else {
+                    &"None"
+                },
+            )
+            .finish()
+    }
+}
+
+impl<State> Display for TlsListener<State> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let https_fmt = |a| format!("https://{}", a);
+        match &self.listener {
+            Some(listener) => {
+                let addr = listener.local_addr().expect("Could not get local addr");
+                write!(f, "{}", https_fmt(&addr))
+            }
+            None => match &self.addrs {
+                Some(addrs) => {
+                    let addrs = addrs.iter().map(https_fmt).collect::<Vec<_>>().join(", ");
+                    write!(f, "{}", addrs)
+                }
+                None => write!(f, "Not listening. Did you forget to call `Listener::bind`?"),
+            },
+        }
+    }
+}
+
+/// `async_h1` requires a cloneable stream, so the TLS session is shared
+/// between the clones.
+#[derive(Clone)]
+struct TlsStream(Arc<Mutex<server::TlsStream<TcpStream>>>);
+
+impl io::Read for TlsStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut stream = self.0.lock().unwrap();
+        Pin::new(&mut *stream).poll_read(cx, buf)
+    }
+}
+
+impl io::Write for TlsStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut stream = self.0.lock().unwrap();
+        Pin::new(&mut *stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut stream = self.0.lock().unwrap();
+        Pin::new(&mut *stream).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut stream = self.0.lock().unwrap();
+        Pin::new(&mut *stream).poll_close(cx)
+    }
+}
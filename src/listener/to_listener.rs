@@ -20,6 +20,10 @@ use async_std::io;
 /// * `http+unix://./socket.file` (also relative path)
 /// * `http+unix://../socket` (relative path)
 ///
+/// # Strings supported only with the `tls` feature:
+/// * `https://localhost:4433?cert=cert.pem&key=key.pem` (PEM encoded certificate chain and key)
+/// * `tls://0.0.0.0?cert=cert.pem&key=key.pem` (binds to port 443 by default; `ssl` is an alias)
+///
 /// # String supported only on windows:
 /// * `:3000` (binds to port 3000)
 ///
@@ -1,3 +1,5 @@
+#[cfg(feature = "tls")]
+use super::TlsListener;
 #[cfg(unix)]
 use super::UnixListener;
 use super::{ConcurrentListener, FailoverListener, ParsedListener, TcpListener, ToListener};
@@ -38,10 +40,38 @@ where
                 self.socket_addrs(|| Some(80))?,
             ))),
 
-            "tls" | "ssl" | "https" => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "parsing TLS listeners not supported yet",
-            )),
+            "tls" | "ssl" | "https" => {
+                #[cfg(feature = "tls")]
+                {
+                    let query = |name| {
+                        self.query_pairs()
+                            .find(|(key, _)| key == name)
+                            .map(|(_, value)| value.into_owned())
+                            .ok_or_else(|| {
+                                io::Error::new(
+                                    io::ErrorKind::InvalidInput,
+                                    format!("TLS listeners require a `{}` query parameter", name),
+                                )
+                            })
+                    };
+                    let cert = query("cert")?;
+                    let key = query("key")?;
+
+                    Ok(ParsedListener::Tls(TlsListener::from_addrs(
+                        self.socket_addrs(|| Some(443))?,
+                        cert,
+                        key,
+                    )))
+                }
+
+                #[cfg(not(feature = "tls"))]
+                {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        "parsing TLS listeners requires the `tls` feature",
+                    ))
+                }
+            }
 
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -198,6 +228,17 @@ where
     }
 }
 
+#[cfg(feature = "tls")]
+impl<State> ToListener<State> for TlsListener<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    type Listener = Self;
+    fn to_listener(self) -> io::Result<Self::Listener> {
+        Ok(self)
+    }
+}
+
 #[cfg(unix)]
 impl<State> ToListener<State> for UnixListener<State>
 where
@@ -339,13 +380,37 @@ mod parse_tests {
         }
     }
 
+    #[cfg(not(feature = "tls"))]
     #[test]
     fn str_tls_parse_and_url() {
         let err = listen("tls://localhost:443").unwrap_err();
-        assert_eq!(err.to_string(), "parsing TLS listeners not supported yet");
+        assert_eq!(
+            err.to_string(),
+            "parsing TLS listeners requires the `tls` feature"
+        );
 
         let err = listen(Url::parse("https://localhost:443").unwrap()).unwrap_err();
-        assert_eq!(err.to_string(), "parsing TLS listeners not supported yet");
+        assert_eq!(
+            err.to_string(),
+            "parsing TLS listeners requires the `tls` feature"
+        );
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn str_tls_parse_and_url() {
+        let listener = listen("tls://localhost:8443?cert=cert.pem&key=key.pem").unwrap();
+        assert!(listener.to_string().contains("https://127.0.0.1:8443"));
+
+        let listener =
+            listen(Url::parse("https://127.0.0.1?cert=a.pem&key=b.pem").unwrap()).unwrap();
+        assert_eq!(listener.to_string(), "https://127.0.0.1:443");
+
+        let err = listen("https://localhost:8443?cert=cert.pem").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "TLS listeners require a `key` query parameter"
+        );
     }
 
     #[test]
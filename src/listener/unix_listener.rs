@@ -1,4 +1,4 @@
-use super::{is_transient_error, ListenInfo};
+use super::{is_transient_error, serve_connection, ListenInfo};
 
 use crate::listener::Listener;
 use crate::{log, Server};
@@ -46,15 +46,13 @@ impl<State> UnixListener<State> {
 }
 
 fn handle_unix<State: Clone + Send + Sync + 'static>(app: Server<State>, stream: UnixStream) {
+    let guard = app.shutdown_handle().connection();
     task::spawn(async move {
+        let _guard = guard;
         let local_addr = unix_socket_addr_to_string(stream.local_addr());
         let peer_addr = unix_socket_addr_to_string(stream.peer_addr());
 
-        let fut = async_h1::accept(stream, |mut req| async {
-            req.set_local_addr(local_addr.as_ref());
-            req.set_peer_addr(peer_addr.as_ref());
-            app.respond(req).await
-        });
+        let fut = serve_connection(app, stream, local_addr, peer_addr, false);
 
         if let Err(error) = fut.await {
             log::error!("async-h1 error", { error: error.to_string() });
@@ -96,9 +94,16 @@ where
             .take()
             .expect("`Listener::bind` must be called before `Listener::accept`");
 
+        let shutdown = server.shutdown_handle();
         let mut incoming = listener.incoming();
 
-        while let Some(stream) = incoming.next().await {
+        let stop = async {
+            shutdown.wait().await;
+            None
+        };
+        futures_util::pin_mut!(stop);
+
+        while let Some(stream) = incoming.next().race(&mut stop).await {
             match stream {
                 Err(ref e) if is_transient_error(e) => continue,
                 Err(error) => {
@@ -113,6 +118,8 @@ where
                 }
             };
         }
+
+        shutdown.drain().await;
         Ok(())
     }
 
@@ -0,0 +1,639 @@
+//! Streaming `multipart/form-data` request bodies.
+//!
+//! Obtain a [`Multipart`] with
+//! [`Request::body_multipart`](crate::Request::body_multipart), then iterate
+//! over its [`Part`]s. Each part is read straight from the request body, so
+//! a part must be read before the next one is requested; any unread
+//! remainder of a part is skipped when moving on to the next one.
+
+use async_std::io::{self, prelude::*};
+use async_std::stream::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::fmt::{self, Debug, Formatter};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::http::{Body, Mime, StatusCode};
+use crate::request::read_error;
+
+/// How much of the body is read at once.
+const CHUNK: usize = 8 * 1024;
+
+/// The maximum size of the headers of a single part.
+const MAX_HEADERS: usize = 8 * 1024;
+
+/// Characters percent-encoded in field names, keeping the brackets of nested
+/// fields.
+const FIELD_NAME: &AsciiSet = &NON_ALPHANUMERIC.remove(b'[').remove(b']');
+
+/// A stream of the parts of a `multipart/form-data` request body.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use async_std::task::block_on;
+/// # fn main() -> Result<(), std::io::Error> { block_on(async {
+/// #
+/// use async_std::fs::File;
+/// use async_std::io;
+/// use async_std::prelude::*;
+/// use tide::Request;
+///
+/// let mut app = tide::new();
+/// app.at("/upload").post(|mut req: Request<()>| async move {
+///     let mut parts = req.body_multipart()?.field_limit(10 * 1024 * 1024);
+///     let mut uploaded = Vec::new();
+///     while let Some(part) = parts.next().await {
+///         let mut part = part?;
+///         if let Some(filename) = part.filename() {
+///             let filename = filename.replace('/', "_");
+///             let mut file = File::create(format!("/tmp/{}", filename)).await?;
+///             // On failure, the next part is the error with its status.
+///             if io::copy(&mut part, &mut file).await.is_ok() {
+///                 uploaded.push(filename);
+///             }
+///         }
+///     }
+///     Ok(format!("Uploaded {}", uploaded.join(", ")))
+/// });
+/// app.listen("127.0.0.1:8080").await?;
+/// #
+/// # Ok(()) }) }
+/// ```
+pub struct Multipart {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// A part of a `multipart/form-data` request body.
+///
+/// The contents of the part are read with [`Part::body_bytes`] and
+/// [`Part::body_string`], or streamed through its [`Read`]
+/// implementation. Read errors caused by an exceeded size limit or a
+/// malformed body are then reported as `io::ErrorKind::InvalidData`, and the
+/// next call to [`Multipart`]'s `next` returns the error with its status.
+pub struct Part {
+    inner: Arc<Mutex<Inner>>,
+    id: usize,
+    name: String,
+    filename: Option<String>,
+    content_type: Option<Mime>,
+}
+
+struct Inner {
+    body: Body,
+    /// `\r\n--` followed by the boundary.
+    delimiter: Vec<u8>,
+    /// Bytes read from `body` but not consumed yet.
+    buf: Vec<u8>,
+    state: State,
+    /// The number of parts returned so far.
+    parts: usize,
+    field_limit: Option<u64>,
+    total_limit: Option<u64>,
+    field_read: u64,
+    total_read: u64,
+    /// A failure hit while reading a part, reported by the stream next.
+    error: Option<Failure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the first delimiter.
+    Preamble,
+    /// Inside the body of the part with the given id.
+    Body(usize),
+    /// Right after a delimiter.
+    Delimiter,
+    /// At the start of the headers of a part.
+    Headers,
+    Done,
+}
+
+#[derive(Debug, Clone)]
+struct Failure {
+    status: StatusCode,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn malformed(message: &str) -> Self {
+        Self::new(
+            StatusCode::BadRequest,
+            format!("Malformed multipart body: {}", message),
+        )
+    }
+}
+
+impl From<Failure> for crate::Error {
+    fn from(failure: Failure) -> Self {
+        crate::Error::from_str(failure.status, failure.message)
+    }
+}
+
+impl From<Failure> for io::Error {
+    fn from(failure: Failure) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, failure.message)
+    }
+}
+
+impl Multipart {
+    /// Parse `body` with the given boundary, without limits.
+    pub(crate) fn new(body: Body, boundary: &str) -> Self {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+        let inner = Inner {
+            body,
+            delimiter,
+            // The first delimiter may start the body, without a line break
+            // before it.
+            buf: b"\r\n".to_vec(),
+            state: State::Preamble,
+            parts: 0,
+            field_limit: None,
+            total_limit: None,
+            field_read: 0,
+            total_read: 0,
+            error: None,
+        };
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Limit the size in bytes of the contents of each part.
+    ///
+    /// Reading past the limit fails, as does moving on to the next part, with
+    /// a `413 Payload Too Large` error.
+    #[must_use]
+    pub fn field_limit(self, limit: u64) -> Self {
+        self.inner.lock().unwrap().field_limit = Some(limit);
+        self
+    }
+
+    /// Limit the total size in bytes of the body.
+    ///
+    /// Reading past the limit fails with a `413 Payload Too Large` error.
+    /// Defaults to the body limit of the request, set with
+    /// [`Server::body_limit`](crate::Server::body_limit) or
+    /// [`Route::body_limit`](crate::Route::body_limit).
+    #[must_use]
+    pub fn total_limit(self, limit: u64) -> Self {
+        self.inner.lock().unwrap().total_limit = Some(limit);
+        self
+    }
+
+    /// Collect the text fields of the body into a struct, skipping file
+    /// uploads.
+    ///
+    /// Fields are deserialized like a URL query, so repeated fields and
+    /// nested fields such as `user[name]` are supported.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the body is malformed or exceeds a limit, if a
+    /// text field is not valid UTF-8, or with status `422 Unprocessable
+    /// Entity` if the fields cannot be deserialized into `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::prelude::*;
+    /// use tide::Request;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Signup {
+    ///     name: String,
+    ///     age: u8,
+    /// }
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/signup").post(|mut req: Request<()>| async move {
+    ///     let signup: Signup = req.body_multipart()?.fields().await?;
+    ///     Ok(format!("Welcome {}, aged {}", signup.name, signup.age))
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub async fn fields<T: DeserializeOwned>(mut self) -> crate::Result<T> {
+        let mut query = String::new();
+        while let Some(part) = self.next().await {
+            let mut part = part?;
+            if part.filename().is_some() {
+                continue;
+            }
+
+            let value = part.body_string().await?;
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.extend(utf8_percent_encode(part.name(), FIELD_NAME));
+            query.push('=');
+            query.extend(utf8_percent_encode(&value, NON_ALPHANUMERIC));
+        }
+
+        serde_qs::from_str(&query).map_err(|e| {
+            crate::Error::from_str(
+                StatusCode::UnprocessableEntity,
+                format!("Invalid multipart fields: {}", e),
+            )
+        })
+    }
+}
+
+impl Inner {
+    /// Read more of the body into `buf`. Running out of body is an error,
+    /// since more is only requested before the closing delimiter.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Failure>> {
+        let len = self.buf.len();
+        self.buf.resize(len + CHUNK, 0);
+        let read = Pin::new(&mut self.body).poll_read(cx, &mut self.buf[len..]);
+        match read {
+            Poll::Ready(Ok(0)) => {
+                self.buf.truncate(len);
+                Poll::Ready(Err(Failure::malformed("unexpected end of body")))
+            }
+            Poll::Ready(Ok(n)) => {
+                self.buf.truncate(len + n);
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(e)) => {
+                self.buf.truncate(len);
+                let error = read_error(e);
+                Poll::Ready(Err(Failure::new(error.status(), error.to_string())))
+            }
+            Poll::Pending => {
+                self.buf.truncate(len);
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Account for `n` more bytes of the body being consumed.
+    fn consume(&mut self, n: usize) -> Result<(), Failure> {
+        let n = n as u64;
+        self.total_read += n;
+        if let Some(limit) = self.total_limit {
+            if self.total_read > limit {
+                return Err(Failure::new(
+                    StatusCode::PayloadTooLarge,
+                    format!("Multipart body exceeds the limit of {} bytes", limit),
+                ));
+            }
+        }
+        if let State::Body(_) = self.state {
+            self.field_read += n;
+            if let Some(limit) = self.field_limit {
+                if self.field_read > limit {
+                    return Err(Failure::new(
+                        StatusCode::PayloadTooLarge,
+                        format!("Multipart field exceeds the limit of {} bytes", limit),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the contents of the current part into `out`, returning `0` once
+    /// the delimiter ending it has been consumed.
+    fn poll_contents(
+        &mut self,
+        cx: &mut Context<'_>,
+        out: &mut [u8],
+    ) -> Poll<Result<usize, Failure>> {
+        loop {
+            let (available, found) = match find(&self.buf, &self.delimiter) {
+                Some(index) => (index, true),
+                // Hold back what could be the start of a delimiter.
+                None => (
+                    self.buf.len().saturating_sub(self.delimiter.len() - 1),
+                    false,
+                ),
+            };
+
+            if available > 0 {
+                let n = available.min(out.len());
+                self.consume(n)?;
+                out[..n].copy_from_slice(&self.buf[..n]);
+                self.buf.drain(..n);
+                return Poll::Ready(Ok(n));
+            }
+
+            if found {
+                let len = self.delimiter.len();
+                self.state = State::Delimiter;
+                self.consume(len)?;
+                self.buf.drain(..len);
+                return Poll::Ready(Ok(0));
+            }
+
+            futures_util::ready!(self.poll_fill(cx))?;
+        }
+    }
+
+    /// Advance to the next part, returning `None` after the last one.
+    fn poll_part(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<PartHeaders>, Failure>> {
+        loop {
+            match self.state {
+                State::Done => return Poll::Ready(Ok(None)),
+                State::Preamble | State::Body(_) => {
+                    let mut skipped = [0; CHUNK];
+                    futures_util::ready!(self.poll_contents(cx, &mut skipped))?;
+                }
+                State::Delimiter => {
+                    // The delimiter is followed by `--` for the last part, or
+                    // by optional whitespace and a line break.
+                    if self.buf.len() < 2 {
+                        futures_util::ready!(self.poll_fill(cx))?;
+                        continue;
+                    }
+                    if self.buf.starts_with(b"--") {
+                        self.state = State::Done;
+                        continue;
+                    }
+                    match find(&self.buf, b"\r\n") {
+                        Some(end) => {
+                            if self.buf[..end].iter().any(|b| *b != b' ' && *b != b'\t') {
+                                return Poll::Ready(Err(Failure::malformed(
+                                    "unexpected data after boundary",
+                                )));
+                            }
+                            self.consume(end + 2)?;
+                            self.buf.drain(..end + 2);
+                            self.state = State::Headers;
+                        }
+                        None if self.buf.len() > MAX_HEADERS => {
+                            return Poll::Ready(Err(Failure::malformed(
+                                "unexpected data after boundary",
+                            )));
+                        }
+                        None => futures_util::ready!(self.poll_fill(cx))?,
+                    }
+                }
+                State::Headers => {
+                    let end = if self.buf.starts_with(b"\r\n") {
+                        Some(0)
+                    } else {
+                        find(&self.buf, b"\r\n\r\n").map(|index| index + 2)
+                    };
+                    let end = match end {
+                        Some(end) => end,
+                        None if self.buf.len() > MAX_HEADERS => {
+                            return Poll::Ready(Err(Failure::malformed("part headers too large")));
+                        }
+                        None => {
+                            futures_util::ready!(self.poll_fill(cx))?;
+                            continue;
+                        }
+                    };
+
+                    let headers = PartHeaders::parse(&self.buf[..end])?;
+                    self.consume(end + 2)?;
+                    self.buf.drain(..end + 2);
+                    self.parts += 1;
+                    self.field_read = 0;
+                    self.state = State::Body(self.parts);
+                    return Poll::Ready(Ok(Some(headers)));
+                }
+            }
+        }
+    }
+}
+
+impl Stream for Multipart {
+    type Item = crate::Result<Part>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(failure) = inner.error.take() {
+            inner.state = State::Done;
+            return Poll::Ready(Some(Err(failure.into())));
+        }
+
+        match futures_util::ready!(inner.poll_part(cx)) {
+            Ok(Some(headers)) => Poll::Ready(Some(Ok(Part {
+                inner: self.inner.clone(),
+                id: inner.parts,
+                name: headers.name,
+                filename: headers.filename,
+                content_type: headers.content_type,
+            }))),
+            Ok(None) => Poll::Ready(None),
+            Err(failure) => {
+                inner.state = State::Done;
+                Poll::Ready(Some(Err(failure.into())))
+            }
+        }
+    }
+}
+
+impl Part {
+    /// The name of the form field.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the uploaded file, if the part is a file upload.
+    #[must_use]
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// The content type of the part, if given.
+    #[must_use]
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// Read the rest of the part into a byte buffer.
+    ///
+    /// # Errors
+    ///
+    /// Unlike reading through [`Read`], the returned error has the
+    /// status of the failure: `413 Payload Too Large` if a limit is exceeded,
+    /// and `400 Bad Request` if the body is malformed.
+    pub async fn body_bytes(&mut self) -> crate::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        match self.read_to_end(&mut bytes).await {
+            Ok(_) => Ok(bytes),
+            Err(e) => Err(self.failure(e)),
+        }
+    }
+
+    /// Read the rest of the part into a string.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Part::body_bytes`], or with status `400 Bad Request` if
+    /// the contents are not valid UTF-8.
+    pub async fn body_string(&mut self) -> crate::Result<String> {
+        let bytes = self.body_bytes().await?;
+        String::from_utf8(bytes).map_err(|_| {
+            crate::Error::from_str(
+                StatusCode::BadRequest,
+                format!("Multipart field `{}` is not valid UTF-8", self.name),
+            )
+        })
+    }
+
+    /// Turn a read error into a response error with the status of the
+    /// recorded failure.
+    fn failure(&self, error: io::Error) -> crate::Error {
+        match self.inner.lock().unwrap().error.take() {
+            Some(failure) => failure.into(),
+            None => crate::Error::new(StatusCode::BadRequest, error),
+        }
+    }
+}
+
+impl io::Read for Part {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != State::Body(self.id) {
+            // The stream has moved past this part.
+            return Poll::Ready(Ok(0));
+        }
+
+        match futures_util::ready!(inner.poll_contents(cx, buf)) {
+            Ok(n) => Poll::Ready(Ok(n)),
+            Err(failure) => {
+                inner.state = State::Done;
+                inner.error = Some(failure.clone());
+                Poll::Ready(Err(failure.into()))
+            }
+        }
+    }
+}
+
+impl Debug for Multipart {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("Multipart")
+            .field("parts", &inner.parts)
+            .field("field_limit", &inner.field_limit)
+            .field("total_limit", &inner.total_limit)
+            .finish()
+    }
+}
+
+impl Debug for Part {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("name", &self.name)
+            .field("filename", &self.filename)
+            .field("content_type", &self.content_type)
+            .finish()
+    }
+}
+
+/// The headers of a part that we care about.
+struct PartHeaders {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<Mime>,
+}
+
+impl PartHeaders {
+    /// Parse a block of `\r\n` terminated header lines.
+    fn parse(block: &[u8]) -> Result<Self, Failure> {
+        let block = std::str::from_utf8(block)
+            .map_err(|_| Failure::malformed("part headers are not valid UTF-8"))?;
+
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = None;
+        for line in block.split("\r\n").filter(|line| !line.is_empty()) {
+            let (header, value) = line
+                .split_once(':')
+                .ok_or_else(|| Failure::malformed("invalid part header"))?;
+            let value = value.trim();
+            if header.eq_ignore_ascii_case("content-disposition") {
+                let params = disposition_params(value)
+                    .ok_or_else(|| Failure::malformed("invalid Content-Disposition"))?;
+                for (key, value) in params {
+                    if key.eq_ignore_ascii_case("name") {
+                        name = Some(value);
+                    } else if key.eq_ignore_ascii_case("filename") {
+                        filename = Some(value);
+                    }
+                }
+            } else if header.eq_ignore_ascii_case("content-type") {
+                content_type = value.parse().ok();
+            }
+        }
+
+        Ok(Self {
+            name: name.ok_or_else(|| Failure::malformed("part without a name"))?,
+            filename,
+            content_type,
+        })
+    }
+}
+
+/// Parse the parameters of a `form-data; name="a"; filename="b"`
+/// disposition, or return `None` if it is not `form-data`.
+fn disposition_params(value: &str) -> Option<Vec<(String, String)>> {
+    let (kind, mut rest) = value.split_once(';').unwrap_or((value, ""));
+    if !kind.trim().eq_ignore_ascii_case("form-data") {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ';' || c.is_whitespace());
+        if rest.is_empty() {
+            return Some(params);
+        }
+        let (key, after) = rest.split_once('=')?;
+        let key = key.trim().to_owned();
+        let after = after.trim_start();
+
+        if let Some(quoted) = after.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (_, '\\') => value.push(chars.next()?.1),
+                    (index, '"') => break index,
+                    (_, c) => value.push(c),
+                }
+            };
+            params.push((key, value));
+            rest = &quoted[end + 1..];
+        } else {
+            let end = after.find(';').unwrap_or(after.len());
+            params.push((key, after[..end].trim().to_owned()));
+            rest = &after[end..];
+        }
+    }
+}
+
+/// The index of the first occurrence of `needle` in `haystack`.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
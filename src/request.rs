@@ -1,9 +1,16 @@
 use async_std::io::{self, prelude::*};
 use async_std::task::{Context, Poll};
-use routefinder::Captures;
+use percent_encoding::{
+    percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC,
+};
+use routefinder::{Captures, RouteSpec, Segment};
 
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::ops::Index;
 use std::pin::Pin;
+use std::str::FromStr;
 
 #[cfg(feature = "cookies")]
 use crate::cookies::CookieData;
@@ -12,8 +19,38 @@ use crate::http::cookies::Cookie;
 use crate::http::format_err;
 use crate::http::headers::{self, HeaderName, HeaderValues, ToHeaderValues};
 use crate::http::{self, Body, Method, Mime, StatusCode, Url, Version};
+use crate::multipart::Multipart;
+use crate::router::{AssetUrls, RouteNames};
 use crate::Response;
 
+/// Characters percent-encoded when a value is placed in a path segment.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters percent-encoded when a value is placed in a wildcard, which may
+/// span several path segments.
+pub(crate) const WILDCARD: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
 pin_project_lite::pin_project! {
     /// An HTTP request.
     ///
@@ -69,6 +106,14 @@ impl<State> Request<State> {
         self.req.method()
     }
 
+    /// Set the request's HTTP method.
+    ///
+    /// Changing the method only affects routing when done by middleware
+    /// added with [`Server::with_pre_routing`](crate::Server::with_pre_routing).
+    pub fn set_method(&mut self, method: Method) {
+        self.req.set_method(method)
+    }
+
     /// Access the request's full URI method.
     ///
     /// # Examples
@@ -93,6 +138,14 @@ impl<State> Request<State> {
         self.req.url()
     }
 
+    /// Get a mutable reference to the request's full URI.
+    ///
+    /// Changing the URL only affects routing when done by middleware added
+    /// with [`Server::with_pre_routing`](crate::Server::with_pre_routing).
+    pub fn url_mut(&mut self) -> &mut Url {
+        self.req.url_mut()
+    }
+
     /// Access the request's HTTP version.
     ///
     /// # Examples
@@ -334,6 +387,252 @@ impl<State> Request<State> {
             .find_map(|captures| captures.wildcard())
     }
 
+    /// Extract a route parameter by name, percent-decode it and parse it
+    /// into `T`.
+    ///
+    /// The name should *not* include the leading `:`.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `key` is not a valid parameter for the route.
+    /// If the parameter is not valid UTF-8 once decoded, or cannot be parsed
+    /// into `T`, the error has status `400 Bad Request`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::{Request, Result};
+    ///
+    /// async fn user(req: Request<()>) -> Result<String> {
+    ///     let id: u64 = req.param_as("id")?;
+    ///     Ok(format!("User #{}", id))
+    /// }
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/users/:id").get(user);
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    pub fn param_as<T>(&self, key: &str) -> crate::Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = decode_param(key, self.param(key)?)?;
+        value.parse().map_err(|e| {
+            crate::Error::from_str(
+                StatusCode::BadRequest,
+                format!("Param \"{}\" is invalid: {}", key, e),
+            )
+        })
+    }
+
+    /// Percent-decode all route parameters and deserialize them into a
+    /// struct.
+    ///
+    /// Every parameter is available under its name, without the leading
+    /// `:`. The route's [`wildcard`](Self::wildcard), if any, is available
+    /// as `wildcard`, so a route with a wildcard can't also have a parameter
+    /// named `:wildcard`. When nested routes capture a parameter with the
+    /// same name, the innermost one wins.
+    ///
+    /// # Errors
+    ///
+    /// An error with status `400 Bad Request` is returned if a parameter is
+    /// not valid UTF-8 once decoded, or if the parameters cannot be
+    /// deserialized into `T`. An error is returned if the route has both a
+    /// wildcard and a parameter named `:wildcard`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::prelude::*;
+    /// use tide::{Request, Result};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Post {
+    ///     user: u64,
+    ///     post: String,
+    /// }
+    ///
+    /// async fn post(req: Request<()>) -> Result<String> {
+    ///     let Post { user, post } = req.params()?;
+    ///     Ok(format!("Post {} by user #{}", post, user))
+    /// }
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/users/:user/posts/:post").get(post);
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    pub fn params<T: serde::de::DeserializeOwned>(&self) -> crate::Result<T> {
+        let mut params = HashMap::new();
+        for captures in &self.route_params {
+            params.extend(captures.iter());
+        }
+        if let Some(wildcard) = self.wildcard() {
+            if params.contains_key("wildcard") {
+                return Err(format_err!(
+                    "Param \"wildcard\" conflicts with the route's wildcard"
+                ));
+            }
+            params.insert("wildcard", wildcard);
+        }
+
+        // Re-encode the decoded values so that `serde_qs` decodes them back
+        // verbatim, including any `+` or `&` they contain.
+        let mut query = String::new();
+        for (key, value) in params {
+            let value = decode_param(key, value)?;
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.extend(utf8_percent_encode(key, NON_ALPHANUMERIC));
+            query.push('=');
+            query.extend(utf8_percent_encode(&value, NON_ALPHANUMERIC));
+        }
+
+        serde_qs::from_str(&query).map_err(|e| {
+            crate::Error::from_str(
+                StatusCode::BadRequest,
+                format!("Invalid route params: {}", e),
+            )
+        })
+    }
+
+    /// Build the path of a route that was named with
+    /// [`Route::name`](crate::Route::name), filling in its parameters.
+    ///
+    /// Parameters are given by name, without the leading `:`. The wildcard
+    /// of a route ending in `*` is given as the `"*"` parameter, and is left
+    /// empty if omitted. Values are percent-encoded.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if no route is named `name`, if a parameter of
+    /// the route is missing from `params`, or if `params` contains a
+    /// parameter the route does not have.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::{Redirect, Request};
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/users/:id").name("user").get(|req: Request<()>| async move {
+    ///     Ok(format!("user {}", req.param("id")?))
+    /// });
+    /// app.at("/me").get(|req: Request<()>| async move {
+    ///     Ok(Redirect::new(req.url_for("user", &[("id", "42")])?))
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> crate::Result<String> {
+        let template = self
+            .ext::<RouteNames>()
+            .and_then(|names| names.0.get(name))
+            .ok_or_else(|| format_err!("No route named \"{}\"", name))?;
+        let spec: RouteSpec = template
+            .parse()
+            .map_err(|e| format_err!("Invalid route \"{}\": {}", name, e))?;
+
+        let value = |key: &str| params.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+
+        let mut path = String::from("/");
+        for segment in spec.segments() {
+            match segment {
+                Segment::Slash => path.push('/'),
+                Segment::Dot => path.push('.'),
+                Segment::Exact(exact) => path.push_str(exact),
+                Segment::Param(param) => {
+                    let value = value(param).ok_or_else(|| {
+                        format_err!("Param \"{}\" missing for route \"{}\"", param, name)
+                    })?;
+                    // A `.` or `..` segment would be resolved away by clients.
+                    if value == "." || value == ".." {
+                        path.push_str(&value.replace('.', "%2E"));
+                    } else {
+                        path.extend(utf8_percent_encode(value, SEGMENT));
+                    }
+                }
+                Segment::Wildcard => {
+                    let value = value("*").unwrap_or_default();
+                    path.extend(utf8_percent_encode(value, WILDCARD));
+                }
+            }
+        }
+
+        for (key, _) in params {
+            let known = spec.segments().iter().any(|segment| match segment {
+                Segment::Param(param) => param == key,
+                Segment::Wildcard => *key == "*",
+                _ => false,
+            });
+            if !known {
+                return Err(format_err!("Route \"{}\" has no param \"{}\"", name, key));
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Get the fingerprinted URL of a static asset, served by a directory
+    /// mounted with the [`fingerprint`](crate::fs::ServeDirOptions::fingerprint)
+    /// option.
+    ///
+    /// The asset is given by its path in the directory, such as `"js/app.js"`,
+    /// or by its full plain URL path, such as `"/assets/js/app.js"`. When
+    /// several directories have a file at the same relative path, the one
+    /// mounted first is used.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if no fingerprinted directory has a file at
+    /// `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::fs::ServeDirOptions;
+    /// use tide::{Request, Response};
+    ///
+    /// let mut app = tide::new();
+    /// let options = ServeDirOptions::new().fingerprint(true);
+    /// app.at("/assets/*").serve_dir_with("assets/", options)?;
+    /// app.at("/").get(|req: Request<()>| async move {
+    ///     let script = req.asset_url("app.js")?;
+    ///     Ok(Response::builder(200)
+    ///         .body(format!("<script src=\"{}\"></script>", script))
+    ///         .content_type(tide::http::mime::HTML)
+    ///         .build())
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) })}
+    /// ```
+    pub fn asset_url(&self, path: &str) -> crate::Result<String> {
+        self.ext::<AssetUrls>()
+            .and_then(|assets| assets.0.get(path))
+            .cloned()
+            .ok_or_else(|| format_err!("No asset \"{}\"", path))
+    }
+
     /// Parse the URL query component into a struct, using [serde_qs](https://docs.rs/serde_qs). To
     /// get the entire query as an unparsed string, use `request.url().query()`.
     ///
@@ -398,6 +697,11 @@ impl<State> Request<State> {
     /// Any I/O error encountered while reading the body is immediately returned
     /// as an `Err`.
     ///
+    /// If the body is larger than the limit set with
+    /// [`Server::body_limit`](crate::Server::body_limit) or
+    /// [`Route::body_limit`](crate::Route::body_limit), an `Err` with status
+    /// `413 Payload Too Large` is returned.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -416,8 +720,8 @@ impl<State> Request<State> {
     /// # Ok(()) })}
     /// ```
     pub async fn body_bytes(&mut self) -> crate::Result<Vec<u8>> {
-        let res = self.req.body_bytes().await?;
-        Ok(res)
+        self.limit_body()?;
+        self.req.body_bytes().await.map_err(body_error)
     }
 
     /// Reads the entire request body into a string.
@@ -430,6 +734,11 @@ impl<State> Request<State> {
     /// Any I/O error encountered while reading the body is immediately returned
     /// as an `Err`.
     ///
+    /// If the body is larger than the limit set with
+    /// [`Server::body_limit`](crate::Server::body_limit) or
+    /// [`Route::body_limit`](crate::Route::body_limit), an `Err` with status
+    /// `413 Payload Too Large` is returned.
+    ///
     /// If the body cannot be interpreted as valid UTF-8, an `Err` is returned.
     ///
     /// # Examples
@@ -450,8 +759,8 @@ impl<State> Request<State> {
     /// # Ok(()) })}
     /// ```
     pub async fn body_string(&mut self) -> crate::Result<String> {
-        let res = self.req.body_string().await?;
-        Ok(res)
+        self.limit_body()?;
+        self.req.body_string().await.map_err(body_error)
     }
 
     /// Reads and deserialized the entire request body via json.
@@ -461,15 +770,33 @@ impl<State> Request<State> {
     /// Any I/O error encountered while reading the body is immediately returned
     /// as an `Err`.
     ///
+    /// If the body is larger than the limit set with
+    /// [`Server::body_limit`](crate::Server::body_limit) or
+    /// [`Route::body_limit`](crate::Route::body_limit), an `Err` with status
+    /// `413 Payload Too Large` is returned.
+    ///
     /// If the body cannot be interpreted as valid json for the target type `T`,
     /// an `Err` is returned.
     pub async fn body_json<T: serde::de::DeserializeOwned>(&mut self) -> crate::Result<T> {
-        let res = self.req.body_json().await?;
-        Ok(res)
+        self.limit_body()?;
+        self.req.body_json().await.map_err(body_error)
     }
 
     /// Parse the request body as a form.
     ///
+    /// # Errors
+    ///
+    /// Any I/O error encountered while reading the body is immediately returned
+    /// as an `Err`, as is a body that is not a valid form for the target type
+    /// `T`.
+    ///
+    /// If the body is larger than the limit set with
+    /// [`Server::body_limit`](crate::Server::body_limit) or
+    /// [`Route::body_limit`](crate::Route::body_limit), an `Err` with status
+    /// `413 Payload Too Large` is returned.
+    ///
+    /// # Examples
+    ///
     /// ```rust
     /// # fn main() -> Result<(), std::io::Error> { async_std::task::block_on(async {
     /// use tide::prelude::*;
@@ -501,8 +828,103 @@ impl<State> Request<State> {
     /// # Ok(()) })}
     /// ```
     pub async fn body_form<T: serde::de::DeserializeOwned>(&mut self) -> crate::Result<T> {
-        let res = self.req.body_form().await?;
-        Ok(res)
+        self.limit_body()?;
+        self.req.body_form().await.map_err(body_error)
+    }
+
+    /// Take the body as a stream of `multipart/form-data` parts.
+    ///
+    /// The body is parsed as it is read, so large file uploads need not be
+    /// buffered in memory. See [`Multipart`](crate::multipart::Multipart)
+    /// for limiting the size of the parts, and for collecting text fields
+    /// into a struct.
+    ///
+    /// # Errors
+    ///
+    /// An `Err` with status `415 Unsupported Media Type` is returned if the
+    /// request is not `multipart/form-data`, and with status `400 Bad
+    /// Request` if the content type has no boundary. If the request declares
+    /// a length larger than the limit set with
+    /// [`Server::body_limit`](crate::Server::body_limit) or
+    /// [`Route::body_limit`](crate::Route::body_limit), an `Err` with status
+    /// `413 Payload Too Large` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use async_std::prelude::*;
+    /// use tide::Request;
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/upload").post(|mut req: Request<()>| async move {
+    ///     let mut parts = req.body_multipart()?;
+    ///     let mut names = Vec::new();
+    ///     while let Some(part) = parts.next().await {
+    ///         names.push(part?.name().to_owned());
+    ///     }
+    ///     Ok(names.join(", "))
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn body_multipart(&mut self) -> crate::Result<Multipart> {
+        let content_type = self
+            .req
+            .content_type()
+            .filter(|mime| mime.essence() == "multipart/form-data")
+            .ok_or_else(|| {
+                crate::Error::from_str(
+                    StatusCode::UnsupportedMediaType,
+                    "Expected a multipart/form-data body",
+                )
+            })?;
+        let boundary = content_type
+            .param("boundary")
+            .map(|boundary| boundary.as_str().trim_matches('"').to_owned())
+            .filter(|boundary| !boundary.is_empty())
+            .ok_or_else(|| {
+                crate::Error::from_str(StatusCode::BadRequest, "Multipart body has no boundary")
+            })?;
+
+        let limit = self.ext::<BodyLimit>().map(|BodyLimit(limit)| *limit);
+        if let Some(limit) = limit {
+            check_body_limit(&self.req, limit)?;
+        }
+
+        let multipart = Multipart::new(self.req.take_body(), &boundary);
+        Ok(match limit {
+            Some(limit) => multipart.total_limit(limit),
+            None => multipart,
+        })
+    }
+
+    /// Enforce the body limit, if any: reject a declared length above it,
+    /// and make reading the body past it fail.
+    fn limit_body(&mut self) -> crate::Result<()> {
+        let limit = match self.ext::<BodyLimit>() {
+            Some(BodyLimit(limit)) => *limit,
+            None => return Ok(()),
+        };
+        check_body_limit(&self.req, limit)?;
+
+        let body = self.req.take_body();
+        let len = body.len();
+        let mime = body.mime().clone();
+        let reader = io::BufReader::new(Limited::new(body, limit));
+        let mut body = Body::from_reader(reader, len);
+        body.set_mime(mime);
+
+        // Keep the request without a content type if it had none.
+        let had_content_type = self.req.header(headers::CONTENT_TYPE).is_some();
+        self.req.set_body(body);
+        if !had_content_type {
+            self.req.remove_header(headers::CONTENT_TYPE);
+        }
+        Ok(())
     }
 
     /// returns a `Cookie` by name of the cookie.
@@ -556,6 +978,112 @@ impl<State> Request<State> {
     }
 }
 
+/// The maximum size of the request body in bytes, enforced when the body is
+/// read through `Request`. Set by `Server::body_limit` and `Route::body_limit`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BodyLimit(pub(crate) u64);
+
+/// Reject requests declaring a `Content-Length` above `limit`.
+pub(crate) fn check_body_limit(req: &http::Request, limit: u64) -> crate::Result<()> {
+    let len = req.len().map(|len| len as u64).or_else(|| {
+        req.header(headers::CONTENT_LENGTH)
+            .and_then(|len| len.as_str().parse().ok())
+    });
+    match len {
+        Some(len) if len > limit => Err(payload_too_large(limit)),
+        _ => Ok(()),
+    }
+}
+
+fn payload_too_large(limit: u64) -> crate::Error {
+    crate::Error::from_str(
+        StatusCode::PayloadTooLarge,
+        format!("Request body exceeds the limit of {} bytes", limit),
+    )
+}
+
+/// The error reading a body past a hard limit on its size, such as the limit
+/// on decompressed bodies.
+#[cfg_attr(not(feature = "compression"), allow(dead_code))]
+#[derive(Debug)]
+pub(crate) struct BodyTooLarge(pub(crate) u64);
+
+impl Display for BodyTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request body exceeds the limit of {} bytes", self.0)
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+/// A body failing to read past `limit` bytes, with a `BodyTooLarge` error.
+pub(crate) struct Limited {
+    body: Body,
+    limit: u64,
+    read: u64,
+}
+
+impl Limited {
+    pub(crate) fn new(body: Body, limit: u64) -> Self {
+        Self {
+            body,
+            limit,
+            read: 0,
+        }
+    }
+}
+
+impl io::Read for Limited {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = futures_util::ready!(Pin::new(&mut self.body).poll_read(cx, buf))?;
+        self.read += n as u64;
+        if self.read > self.limit {
+            let error = BodyTooLarge(self.limit);
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, error)));
+        }
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Turn an error reading the request body into a `413 Payload Too Large` if
+/// the body hit a hard size limit, or a `400 Bad Request` otherwise.
+pub(crate) fn read_error(error: io::Error) -> crate::Error {
+    match error
+        .get_ref()
+        .and_then(|error| error.downcast_ref::<BodyTooLarge>())
+    {
+        Some(BodyTooLarge(limit)) => payload_too_large(*limit),
+        None => crate::Error::new(StatusCode::BadRequest, error),
+    }
+}
+
+/// Turn an error reading the whole body into a `413 Payload Too Large` if
+/// the body hit its size limit, keeping any other error as is.
+fn body_error(error: crate::Error) -> crate::Error {
+    match error
+        .downcast_ref::<io::Error>()
+        .and_then(|error| error.get_ref())
+        .and_then(|error| error.downcast_ref::<BodyTooLarge>())
+    {
+        Some(BodyTooLarge(limit)) => payload_too_large(*limit),
+        None => error,
+    }
+}
+
+/// Percent-decode the raw value of a route parameter.
+fn decode_param<'a>(key: &str, value: &'a str) -> crate::Result<Cow<'a, str>> {
+    percent_decode_str(value).decode_utf8().map_err(|_| {
+        crate::Error::from_str(
+            StatusCode::BadRequest,
+            format!("Param \"{}\" is not valid UTF-8", key),
+        )
+    })
+}
+
 impl<State> AsRef<http::Request> for Request<State> {
     fn as_ref(&self) -> &http::Request {
         &self.req
@@ -1,14 +1,17 @@
 use async_std::task;
-use cap_async_std::ambient_authority;
-use cap_async_std::fs::Dir;
+use percent_encoding::utf8_percent_encode;
+use regex::Regex;
+use routefinder::{RouteSpec, Segment};
 use std::fmt::Debug;
 use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::endpoint::MiddlewareEndpoint;
-use crate::fs::{ServeDir, ServeFile};
+use crate::endpoint::{DynEndpoint, MiddlewareEndpoint};
+use crate::fs::{IntoStaticFs, ServeDir, ServeDirOptions, ServeFile};
 use crate::log;
+use crate::request::{check_body_limit, BodyLimit, WILDCARD};
+use crate::server::Projected;
 use crate::{router::Router, Endpoint, Middleware};
 
 /// A handle to a route.
@@ -24,6 +27,10 @@ pub struct Route<'a, State> {
     router: &'a mut Router<State>,
     path: String,
     middleware: Vec<Arc<dyn Middleware<State>>>,
+    constraints: Vec<(String, Regex)>,
+    /// The body size limit of endpoints registered afterwards, set by
+    /// [`body_limit`](Self::body_limit).
+    body_limit: Option<u64>,
     /// Indicates whether the path of current route is treated as a prefix. Set by
     /// [`strip_prefix`].
     ///
@@ -37,26 +44,20 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
             router,
             path,
             middleware: Vec::new(),
+            constraints: Vec::new(),
+            body_limit: None,
             prefix: false,
         }
     }
 
     /// Extend the route with the given `path`.
     pub fn at<'b>(&'b mut self, path: &str) -> Route<'b, State> {
-        let mut p = self.path.clone();
-
-        if !p.ends_with('/') && !path.starts_with('/') {
-            p.push('/');
-        }
-
-        if path != "/" {
-            p.push_str(path);
-        }
-
         Route {
             router: self.router,
-            path: p,
+            path: join_path(&self.path, path),
             middleware: self.middleware.clone(),
+            constraints: self.constraints.clone(),
+            body_limit: self.body_limit,
             prefix: false,
         }
     }
@@ -67,6 +68,72 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
         &self.path
     }
 
+    /// Give the current path a name, so that URLs pointing at it can be built
+    /// with [`Request::url_for`](crate::Request::url_for).
+    ///
+    /// Names of routes inside a nested [`Server`](crate::Server) are
+    /// available to the outer server, resolving to the full path including
+    /// the mount prefix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if another route was already registered with the same name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut app = tide::new();
+    /// app.at("/users/:id")
+    ///     .name("user")
+    ///     .get(|_| async { Ok("a user") });
+    /// app.at("/").get(|req: tide::Request<()>| async move {
+    ///     Ok(tide::Redirect::new(req.url_for("user", &[("id", "42")])?))
+    /// });
+    /// ```
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.router.name(name, &self.path);
+        self
+    }
+
+    /// Only match requests where the route parameter `param` matches the
+    /// regular expression `pattern`.
+    ///
+    /// The pattern must match the whole, percent-decoded parameter. Requests
+    /// it rejects fall through to the other routes matching the path, or to
+    /// the not found endpoint. Like [`Route::with`], constraints apply to the
+    /// endpoints registered on this route afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` is not a valid regular expression, or if the path
+    /// has no parameter named `param`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let mut app = tide::new();
+    /// app.at("/users/:id")
+    ///     .constrain("id", r"\d+")
+    ///     .get(|_| async { Ok("a user id") });
+    /// app.at("/users/:name").get(|_| async { Ok("a user name") });
+    /// ```
+    pub fn constrain(&mut self, param: &str, pattern: &str) -> &mut Self {
+        let has_param = self.path.parse::<RouteSpec>().is_ok_and(|spec| {
+            spec.segments()
+                .iter()
+                .any(|segment| matches!(segment, Segment::Param(p) if p == param))
+        });
+        assert!(
+            has_param,
+            "Route `{}` has no param `{}` to constrain",
+            self.path, param
+        );
+        let regex = Regex::new(&format!("^(?:{})$", pattern))
+            .unwrap_or_else(|e| panic!("Invalid constraint for param `{}`: {}", param, e));
+        self.constraints.push((param.to_owned(), regex));
+        self
+    }
+
     /// Treat the current path as a prefix, and strip prefixes from requests.
     ///
     /// This method is marked unstable as its name might change in the near future.
@@ -93,6 +160,65 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
         self
     }
 
+    /// Register a group of routes sharing middleware.
+    ///
+    /// The closure is given a route at the current path. Middleware added to
+    /// it with [`Route::with`] applies to every route registered inside the
+    /// closure, but not to the current route or to routes registered after
+    /// the group. Unlike [`Route::nest`], the routes are registered directly
+    /// on this server, so they are listed by
+    /// [`Server::routes`](crate::Server::routes) and routed without an extra
+    /// lookup.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::utils::After;
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/admin").group(|admin| {
+    ///     admin.with(After(|mut res: tide::Response| async move {
+    ///         res.insert_header("Cache-Control", "no-store");
+    ///         Ok(res)
+    ///     }));
+    ///     admin.at("/users").get(|_| async { Ok("users") });
+    ///     admin.at("/settings").get(|_| async { Ok("settings") });
+    /// });
+    /// app.at("/").get(|_| async { Ok("cacheable") });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn group<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut Route<'_, State>),
+    {
+        let mut group = Route {
+            router: &mut *self.router,
+            path: self.path.clone(),
+            middleware: self.middleware.clone(),
+            constraints: self.constraints.clone(),
+            body_limit: self.body_limit,
+            prefix: self.prefix,
+        };
+        f(&mut group);
+        self
+    }
+
+    /// Set the maximum size in bytes of request bodies read by the endpoints
+    /// registered on this route afterwards, overriding
+    /// [`Server::body_limit`](crate::Server::body_limit).
+    ///
+    /// Requests declaring a larger `Content-Length` are rejected with
+    /// `413 Payload Too Large` before reaching the endpoint.
+    pub fn body_limit(&mut self, limit: u64) -> &mut Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
     /// Reset the middleware chain for the current route, if any.
     pub fn reset_middleware(&mut self) -> &mut Self {
         self.middleware.clear();
@@ -132,10 +258,118 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
         State: Clone + Send + Sync + 'static,
         InnerState: Clone + Send + Sync + 'static,
     {
+        self.mount(
+            service.router(),
+            service.middleware_names(),
+            service.clone(),
+        )
+    }
+
+    /// Nest a sub-app with its own state type at the current path, deriving
+    /// its state from the state of this server on every request.
+    ///
+    /// `build` adds the routes of the sub-app to the route it is given, which
+    /// is rooted at the current path, and `project` derives the state of each
+    /// request handled by the sub-app from the state of this server. This
+    /// lets a reusable sub-app declare the state it needs, and be mounted
+    /// into any app that can supply it.
+    ///
+    /// Like with [`Route::nest`], the outer server has precedence when
+    /// disambiguating overlapping paths, and the middleware of this server
+    /// also runs for requests handled by the sub-app.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::{Request, Route};
+    ///
+    /// /// The state of a reusable admin panel.
+    /// #[derive(Clone)]
+    /// struct AdminState {
+    ///     site_name: String,
+    /// }
+    ///
+    /// fn admin(route: &mut Route<'_, AdminState>) {
+    ///     route.at("/").get(|req: Request<AdminState>| async move {
+    ///         Ok(format!("Administering {}", req.state().site_name))
+    ///     });
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct AppState {
+    ///     name: String,
+    /// }
+    ///
+    /// let mut app = tide::with_state(AppState {
+    ///     name: "Nori's blog".into(),
+    /// });
+    /// app.at("/admin").nest_with(admin, |state: &AppState| AdminState {
+    ///     site_name: state.name.clone(),
+    /// });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn nest_with<InnerState, B, F>(&mut self, build: B, project: F) -> &mut Self
+    where
+        InnerState: Clone + Send + Sync + 'static,
+        B: FnOnce(&mut Route<'_, InnerState>),
+        F: Fn(&State) -> InnerState + Send + Sync + 'static,
+    {
+        let mut router = Router::new();
+        build(&mut Route::new(&mut router, String::new()));
+        let router = Arc::new(router);
+        self.mount(&router, Vec::new(), Projected::new(router.clone(), project))
+    }
+
+    /// Register `ep`, the endpoint handling requests for the routes of
+    /// `service`, at the current path. `service_middleware` names the
+    /// server-level middleware of the nested server.
+    fn mount<InnerState>(
+        &mut self,
+        service: &Router<InnerState>,
+        service_middleware: Vec<String>,
+        ep: impl Endpoint<State>,
+    ) -> &mut Self
+    where
+        InnerState: Clone + Send + Sync + 'static,
+    {
+        let mut middleware = middleware_names(&self.middleware);
+        middleware.extend(service_middleware);
+        let nested = service
+            .routes()
+            .map(|route| RouteInfo {
+                path: join_path(&self.path, &route.path),
+                middleware: middleware
+                    .iter()
+                    .chain(route.middleware.iter())
+                    .cloned()
+                    .collect(),
+                ..route.clone()
+            })
+            .collect();
+
+        for (name, path) in service.names().iter() {
+            self.router.name(name, &join_path(&self.path, path));
+        }
+        // Relative asset paths are kept as is, since they name files rather
+        // than URLs.
+        for (path, url) in service.assets().iter() {
+            let url = join_path(&self.path, url);
+            if path.starts_with('/') {
+                self.router.asset(&join_path(&self.path, path), &url);
+            } else {
+                self.router.asset(path, &url);
+            }
+        }
+
         let prefix = self.prefix;
 
         self.prefix = true;
-        self.all(service);
+        self.register(None, ep, Some(nested));
         self.prefix = prefix;
 
         self
@@ -143,14 +377,28 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
 
     /// Serve a directory statically.
     ///
-    /// Each file will be streamed from disk, and a mime type will be determined
-    /// based on magic bytes.
+    /// Each file will be streamed from disk, with a mime type determined by
+    /// its extension. Requests for a directory are served its `index.html`
+    /// file, and redirected to the path with a trailing slash if it is
+    /// missing. Responses carry `Last-Modified` and `ETag` headers, so
+    /// [`ConditionalMiddleware`](crate::conditional::ConditionalMiddleware)
+    /// can answer requests for unchanged files with `304 Not Modified`.
+    ///
+    /// The directory is either a path on disk, or any
+    /// [`StaticFs`](crate::fs::StaticFs), such as a
+    /// [`MemoryFs`](crate::fs::MemoryFs) of files embedded in the binary.
+    /// Use [`serve_dir_with`](Self::serve_dir_with) to configure how the
+    /// directory is served, for example to list directories without an index
+    /// file.
     ///
     /// # Security
     ///
     /// This handler ensures no folders outside the specified folder can be
     /// served, and attempts to access any path outside this folder (no matter
     /// if it exists or not) will return `StatusCode::Forbidden` to the caller.
+    /// Hidden files and directories, whose name starts with a dot, are
+    /// answered with `StatusCode::NotFound` unless
+    /// [`ServeDirOptions::hidden`] is set.
     ///
     /// # Examples
     ///
@@ -166,19 +414,87 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
     ///     Ok(())
     /// }
     /// ```
-    pub fn serve_dir(&mut self, dir: impl AsRef<Path>) -> io::Result<()> {
+    pub fn serve_dir(&mut self, dir: impl IntoStaticFs) -> io::Result<()> {
+        self.serve_dir_with(dir, ServeDirOptions::new())
+    }
+
+    /// Serve a directory statically, with the given options.
+    ///
+    /// See [`serve_dir`](Self::serve_dir) and [`ServeDirOptions`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the directory can't be opened, or if
+    /// [fingerprinting](ServeDirOptions::fingerprint) is enabled and either
+    /// reading the files fails or the route has params, which links to the
+    /// fingerprinted files couldn't fill in.
+    ///
+    /// # Blocking
+    ///
+    /// With fingerprinting enabled, every file is read and hashed before this
+    /// returns, blocking the current thread. Mount large directories before
+    /// the server starts handling requests.
+    ///
+    /// # Examples
+    ///
+    /// Serve `.mjs` files as plain text, and directories with their
+    /// `README.md` file.
+    ///
+    /// ```no_run
+    /// use tide::fs::ServeDirOptions;
+    /// use tide::http::mime;
+    ///
+    /// #[async_std::main]
+    /// async fn main() -> Result<(), std::io::Error> {
+    ///     let options = ServeDirOptions::new()
+    ///         .mime_type("mjs", mime::PLAIN)
+    ///         .index_files(["README.md"]);
+    ///
+    ///     let mut app = tide::new();
+    ///     app.at("/docs/*").serve_dir_with("docs/", options)?;
+    ///     app.listen("127.0.0.1:8080").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn serve_dir_with(
+        &mut self,
+        dir: impl IntoStaticFs,
+        options: ServeDirOptions,
+    ) -> io::Result<()> {
         // Verify path exists, return error if it doesn't.
-        let path = dir.as_ref().to_owned().canonicalize()?;
-        let dir = task::block_on(async { Dir::open_ambient_dir(path, ambient_authority()).await })?;
-        let prefix = self.path().to_string();
-        self.get(ServeDir::new(prefix, dir));
+        let fs = dir.into_static_fs()?;
+        let fingerprint = options.fingerprint;
+        if fingerprint && self.has_params() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Route `{}` has params, so fingerprinted asset URLs can't be built",
+                    self.path
+                ),
+            ));
+        }
+        let mut serve_dir = ServeDir::new(fs, options);
+        if fingerprint {
+            task::block_on(serve_dir.fingerprint())?;
+            let base = match self.path.trim_end_matches('*').trim_matches('/') {
+                "" => "/".to_owned(),
+                path => format!("/{}/", path),
+            };
+            for (path, hashed) in serve_dir.manifest().iter() {
+                let url = format!("{}{}", base, utf8_percent_encode(hashed, WILDCARD));
+                self.router.asset(path, &url);
+                self.router.asset(&format!("{}{}", base, path), &url);
+            }
+        }
+        self.get(serve_dir);
         Ok(())
     }
 
     /// Serve a static file.
     ///
     /// The file will be streamed from disk, and a mime type will be determined
-    /// based on magic bytes. Similar to serve_dir
+    /// based on magic bytes. Similar to serve_dir, responses carry
+    /// `Last-Modified` and `ETag` headers.
     pub fn serve_file(&mut self, file: impl AsRef<Path>) -> io::Result<()> {
         self.get(ServeFile::init(file)?);
         Ok(())
@@ -186,21 +502,7 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
 
     /// Add an endpoint for the given HTTP method
     pub fn method(&mut self, method: http_types::Method, ep: impl Endpoint<State>) -> &mut Self {
-        if self.prefix {
-            let ep = StripPrefixEndpoint::new(ep);
-            let wildcard = self.at("*");
-            wildcard.router.add(
-                &wildcard.path,
-                method,
-                MiddlewareEndpoint::wrap_with_middleware(ep, &wildcard.middleware),
-            );
-        } else {
-            self.router.add(
-                &self.path,
-                method,
-                MiddlewareEndpoint::wrap_with_middleware(ep, &self.middleware),
-            );
-        }
+        self.register(Some(method), ep, None);
         self
     }
 
@@ -208,20 +510,135 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
     ///
     /// Routes with specific HTTP methods will be tried first.
     pub fn all(&mut self, ep: impl Endpoint<State>) -> &mut Self {
+        self.register(None, ep, None);
+        self
+    }
+
+    /// Add an endpoint for the given HTTP method, checking that it can be
+    /// reached first.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the path is not a valid route, or if a route
+    /// registered earlier for the same method matches exactly the same paths
+    /// without [constraints](Self::constrain), so that the endpoint could
+    /// never be selected. Routes for other methods, and constrained routes,
+    /// whose rejected requests fall through, don't conflict.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), tide::RouteError> {
+    /// use tide::http::Method;
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/users/:id").name("user").get(|_| async { Ok("a user") });
+    /// app.try_at("/users/:user_id")?
+    ///     .try_method(Method::Put, |_| async { Ok("updated") })?;
+    ///
+    /// let err = app
+    ///     .try_at("/users/:name")?
+    ///     .try_method(Method::Get, |_| async { Ok("a user name") })
+    ///     .err()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Route `GET /users/:name` is unreachable: \
+    ///      route `user` (`GET /users/:id`) matches the same requests first"
+    /// );
+    /// # Ok(()) }
+    /// ```
+    pub fn try_method(
+        &mut self,
+        method: http_types::Method,
+        ep: impl Endpoint<State>,
+    ) -> Result<&mut Self, RouteError> {
+        self.router
+            .check_endpoint(&self.endpoint_path(), Some(method))?;
+        Ok(self.method(method, ep))
+    }
+
+    /// Add an endpoint for all HTTP methods, as a fallback, checking that it
+    /// can be reached first.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the path is not a valid route, or if a route
+    /// registered earlier for all methods matches exactly the same paths
+    /// without [constraints](Self::constrain). See
+    /// [`try_method`](Self::try_method).
+    pub fn try_all(&mut self, ep: impl Endpoint<State>) -> Result<&mut Self, RouteError> {
+        self.router.check_endpoint(&self.endpoint_path(), None)?;
+        Ok(self.all(ep))
+    }
+
+    /// Whether the path of this route has params.
+    fn has_params(&self) -> bool {
+        self.path.parse::<RouteSpec>().is_ok_and(|spec| {
+            spec.segments()
+                .iter()
+                .any(|segment| matches!(segment, Segment::Param(_)))
+        })
+    }
+
+    /// The path endpoints of this route are registered at.
+    fn endpoint_path(&self) -> String {
+        if self.prefix {
+            join_path(&self.path, "*")
+        } else {
+            self.path.clone()
+        }
+    }
+
+    /// Register `ep` for `method`, or for all methods if `method` is `None`.
+    /// `nested` lists the routes of a nested `Server`, already relative to
+    /// the root of this router.
+    fn register(
+        &mut self,
+        method: Option<http_types::Method>,
+        ep: impl Endpoint<State>,
+        nested: Option<Vec<RouteInfo>>,
+    ) {
+        let ep = BodyLimitEndpoint {
+            endpoint: ep,
+            limit: self.body_limit,
+        };
         if self.prefix {
             let ep = StripPrefixEndpoint::new(ep);
-            let wildcard = self.at("*");
-            wildcard.router.add_all(
-                &wildcard.path,
-                MiddlewareEndpoint::wrap_with_middleware(ep, &wildcard.middleware),
-            );
+            let mut wildcard = self.at("*");
+            let ep = MiddlewareEndpoint::wrap_with_middleware(ep, &wildcard.middleware);
+            wildcard.insert(method, ep, nested);
         } else {
-            self.router.add_all(
-                &self.path,
-                MiddlewareEndpoint::wrap_with_middleware(ep, &self.middleware),
-            );
+            let ep = MiddlewareEndpoint::wrap_with_middleware(ep, &self.middleware);
+            self.insert(method, ep, nested);
+        }
+    }
+
+    fn insert(
+        &mut self,
+        method: Option<http_types::Method>,
+        ep: Box<DynEndpoint<State>>,
+        nested: Option<Vec<RouteInfo>>,
+    ) {
+        self.router.describe(RouteInfo {
+            host: None,
+            path: self.path.clone(),
+            method,
+            middleware: middleware_names(&self.middleware),
+            nested: nested.is_some(),
+        });
+        for route in nested.into_iter().flatten() {
+            self.router.describe(route);
+        }
+
+        let constraints = self.constraints.clone();
+        let added = match method {
+            Some(method) => self.router.add(&self.path, method, ep, constraints),
+            None => self.router.add_all(&self.path, ep, constraints),
+        };
+        if let Err(e) = added {
+            panic!("{}", e);
         }
-        self
     }
 
     /// Add an endpoint for `GET` requests
@@ -255,6 +672,10 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
     }
 
     /// Add an endpoint for `OPTIONS` requests
+    ///
+    /// Paths without an `OPTIONS` endpoint answer `OPTIONS` requests with
+    /// `204 No Content` and an `Allow` header listing the methods registered
+    /// for the path.
     pub fn options(&mut self, ep: impl Endpoint<State>) -> &mut Self {
         self.method(http_types::Method::Options, ep);
         self
@@ -279,6 +700,176 @@ impl<'a, State: Clone + Send + Sync + 'static> Route<'a, State> {
     }
 }
 
+/// An error registering a route, returned by
+/// [`Server::try_at`](crate::Server::try_at), [`Route::try_method`] and
+/// [`Route::try_all`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RouteError {
+    /// The path is not a valid route.
+    Invalid {
+        /// The path that was registered.
+        path: String,
+        /// Why the path is invalid.
+        message: String,
+    },
+    /// The endpoint can never be reached, because a route registered earlier
+    /// for the same method matches exactly the same paths without
+    /// constraints.
+    Conflict {
+        /// The path that was registered.
+        path: String,
+        /// The method of the endpoint, or `None` for all methods.
+        method: Option<http_types::Method>,
+        /// The path of the registered route.
+        existing: String,
+        /// The name of the registered route, if it has one.
+        name: Option<String>,
+    },
+}
+
+impl std::fmt::Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid { path, message } => write!(f, "Invalid route `{}`: {}", path, message),
+            Self::Conflict {
+                path,
+                method,
+                existing,
+                name,
+            } => {
+                let method = match method {
+                    Some(method) => method.as_ref(),
+                    None => "*",
+                };
+                write!(f, "Route `{} {}` is unreachable: ", method, path)?;
+                if let Some(name) = name {
+                    write!(f, "route `{}` (`{} {}`)", name, method, existing)?;
+                } else {
+                    write!(f, "`{} {}`", method, existing)?;
+                }
+                write!(f, " matches the same requests first")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+/// A description of a route registered on a [`Server`], as listed by
+/// [`Server::routes`].
+///
+/// [`Server`]: crate::Server
+/// [`Server::routes`]: crate::Server::routes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    host: Option<String>,
+    path: String,
+    method: Option<http_types::Method>,
+    middleware: Vec<String>,
+    nested: bool,
+}
+
+impl RouteInfo {
+    /// The host pattern the route was registered for with
+    /// [`Server::host`](crate::Server::host), or `None` if it serves all
+    /// other hosts.
+    #[must_use]
+    pub fn host(&self) -> Option<&str> {
+        self.host.as_deref()
+    }
+
+    pub(crate) fn set_host(&mut self, host: &str) {
+        self.host = Some(host.to_owned());
+    }
+
+    /// The path template of the route, e.g. `/users/:id`.
+    ///
+    /// Routes of a nested [`Server`](crate::Server) are reported with the
+    /// path they were mounted at as a prefix.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The HTTP method of the route, or `None` if it handles all methods.
+    #[must_use]
+    pub fn method(&self) -> Option<http_types::Method> {
+        self.method
+    }
+
+    /// The names of the middleware applied to this route, in the order they
+    /// run, as given by [`Middleware::name`].
+    ///
+    /// Middleware added with [`Server::with`](crate::Server::with) on the
+    /// server `routes` is called on applies to every route and is not listed.
+    /// For routes of a nested server this includes the middleware of the
+    /// route it was mounted on and the nested server's own middleware.
+    #[must_use]
+    pub fn middleware(&self) -> &[String] {
+        &self.middleware
+    }
+
+    /// Is this the mount point of a nested [`Server`](crate::Server)?
+    #[must_use]
+    pub fn is_nested(&self) -> bool {
+        self.nested
+    }
+}
+
+impl std::fmt::Display for RouteInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.method {
+            Some(method) => write!(f, "{} ", method)?,
+            None => write!(f, "* ")?,
+        }
+        if let Some(host) = &self.host {
+            write!(f, "{}", host)?;
+        }
+        write!(f, "{}", self.path)
+    }
+}
+
+fn join_path(base: &str, path: &str) -> String {
+    let mut p = base.to_owned();
+
+    if !p.ends_with('/') && !path.starts_with('/') {
+        p.push('/');
+    }
+
+    if path != "/" {
+        p.push_str(path);
+    }
+
+    p
+}
+
+fn middleware_names<State: 'static>(middleware: &[Arc<dyn Middleware<State>>]) -> Vec<String> {
+    middleware.iter().map(|m| m.name().to_owned()).collect()
+}
+
+/// An endpoint with the body size limit of its route, if any.
+#[derive(Debug)]
+struct BodyLimitEndpoint<E> {
+    endpoint: E,
+    limit: Option<u64>,
+}
+
+#[async_trait::async_trait]
+impl<State, E> Endpoint<State> for BodyLimitEndpoint<E>
+where
+    State: Clone + Send + Sync + 'static,
+    E: Endpoint<State>,
+{
+    async fn call(&self, mut req: crate::Request<State>) -> crate::Result {
+        if let Some(limit) = self.limit {
+            check_body_limit(req.as_ref(), limit)?;
+            req.set_ext(BodyLimit(limit));
+        }
+        self.endpoint.call(req).await
+    }
+}
+
 #[derive(Debug)]
 struct StripPrefixEndpoint<E>(std::sync::Arc<E>);
 
@@ -1,7 +1,11 @@
-use routefinder::{Captures, Router as MethodRouter};
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use routefinder::{Capture, Captures, Match, RouteSpec, Router as MethodRouter, Segment};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::endpoint::DynEndpoint;
+use crate::route::{RouteError, RouteInfo};
 use crate::{Request, Response, StatusCode};
 
 /// The routing table used by `Server`
@@ -10,10 +14,71 @@ use crate::{Request, Response, StatusCode};
 /// by the method first allows the table itself to be more efficient.
 #[allow(missing_debug_implementations)]
 pub(crate) struct Router<State> {
-    method_map: HashMap<http_types::Method, MethodRouter<Box<DynEndpoint<State>>>>,
-    all_method_router: MethodRouter<Box<DynEndpoint<State>>>,
+    method_map: HashMap<http_types::Method, MethodRouter<RouteEntry<State>>>,
+    all_method_router: MethodRouter<RouteEntry<State>>,
+    not_found: Box<DynEndpoint<State>>,
+    method_not_allowed: Box<DynEndpoint<State>>,
+    routes: Vec<RouteInfo>,
+    /// The endpoints added to the routing tables, in registration order.
+    endpoints: Vec<Registered>,
+    names: Arc<HashMap<String, String>>,
+    assets: Arc<HashMap<String, String>>,
+    /// The host pattern this router serves, for the routers in `hosts`.
+    host: Option<String>,
+    hosts: Vec<(HostPattern, Router<State>)>,
 }
 
+/// An endpoint in the routing table, together with the constraints its
+/// route parameters must satisfy for it to be selected.
+struct RouteEntry<State> {
+    endpoint: Box<DynEndpoint<State>>,
+    constraints: Vec<(String, Regex)>,
+}
+
+impl<State> RouteEntry<State> {
+    /// Do the percent-decoded `captures` satisfy every constraint?
+    fn accepts(&self, captures: &Captures<'_, '_>) -> bool {
+        self.constraints.iter().all(|(param, regex)| {
+            captures
+                .get(param)
+                .and_then(|value| percent_decode_str(value).decode_utf8().ok())
+                .is_some_and(|value| regex.is_match(&value))
+        })
+    }
+}
+
+/// An endpoint added to the routing tables, as far as conflict detection is
+/// concerned.
+struct Registered {
+    path: String,
+    method: Option<http_types::Method>,
+    constrained: bool,
+}
+
+/// The first match for `path` in `router` whose constraints are satisfied.
+/// Routes with the same shape are tried in registration order, so a route
+/// rejected by its constraints falls through to the next candidate.
+fn find<'a, 'b, State>(
+    router: &'a MethodRouter<RouteEntry<State>>,
+    path: &'b str,
+) -> Option<Match<'a, 'b, RouteEntry<State>>> {
+    router
+        .match_iter(path)
+        .find(|m| m.handler().accepts(&m.captures()))
+}
+
+/// The named routes of the outermost `Server` handling a request, mapping
+/// each name to its path template. Stored as a request extension so
+/// `Request::url_for` can find it.
+#[derive(Debug, Clone)]
+pub(crate) struct RouteNames(pub(crate) Arc<HashMap<String, String>>);
+
+/// The fingerprinted assets of the outermost `Server` handling a request,
+/// mapping the paths of files to their fingerprinted URLs. Stored as a
+/// request extension so `Request::asset_url` can find it.
+#[derive(Debug, Clone)]
+pub(crate) struct AssetUrls(pub(crate) Arc<HashMap<String, String>>);
+
 impl<State> std::fmt::Debug for Router<State> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Router")
@@ -27,6 +92,69 @@ impl<State> std::fmt::Debug for Router<State> {
 pub(crate) struct Selection<'a, State> {
     pub(crate) endpoint: &'a DynEndpoint<State>,
     pub(crate) params: Captures<'static, 'static>,
+    /// The value of the `Allow` header to attach to the response, set when
+    /// the path exists but does not accept the requested method, or when an
+    /// `OPTIONS` request is answered automatically.
+    pub(crate) allow: Option<String>,
+    /// The named routes of the router that handled the request.
+    pub(crate) names: &'a Arc<HashMap<String, String>>,
+    /// The fingerprinted assets of the router that handled the request.
+    pub(crate) assets: &'a Arc<HashMap<String, String>>,
+}
+
+/// A host to route on, either exact or matching any single subdomain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    Exact(String),
+    /// The suffix following the `*` of `*.example.com`, i.e. `.example.com`.
+    Subdomain(String),
+}
+
+impl HostPattern {
+    fn parse(pattern: &str) -> Self {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_prefix('*') {
+            Some(suffix) if suffix.starts_with('.') && !suffix.contains('*') => {
+                Self::Subdomain(suffix.to_owned())
+            }
+            _ => {
+                assert!(
+                    !pattern.contains('*'),
+                    "Invalid host pattern `{}`, only a leading `*.` wildcard is supported",
+                    pattern
+                );
+                Self::Exact(pattern)
+            }
+        }
+    }
+
+    /// Match `host`, without its port, returning the captured subdomain if
+    /// any.
+    fn matches(&self, host: &str) -> Option<Captures<'static, 'static>> {
+        let host = host.to_ascii_lowercase();
+        match self {
+            Self::Exact(exact) if *exact == host => Some(Captures::default()),
+            Self::Subdomain(suffix) => {
+                let subdomain = host.strip_suffix(suffix.as_str())?;
+                if subdomain.is_empty() || subdomain.contains('.') {
+                    return None;
+                }
+                let mut captures = Captures::default();
+                captures.push(Capture::new("subdomain", subdomain.to_owned()));
+                Some(captures)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Strip the port, if any, from a `host[:port]` value.
+fn strip_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        // An IPv6 address, which contains colons itself.
+        return host.split_inclusive(']').next().unwrap_or(host);
+    }
+    host.split(':').next().unwrap_or(host)
 }
 
 impl<State: Clone + Send + Sync + 'static> Router<State> {
@@ -34,7 +162,124 @@ impl<State: Clone + Send + Sync + 'static> Router<State> {
         Router {
             method_map: HashMap::default(),
             all_method_router: MethodRouter::new(),
+            not_found: Box::new(not_found_endpoint),
+            method_not_allowed: Box::new(method_not_allowed),
+            routes: Vec::new(),
+            endpoints: Vec::new(),
+            names: Arc::default(),
+            assets: Arc::default(),
+            host: None,
+            hosts: Vec::new(),
+        }
+    }
+
+    /// The router for requests to hosts matching `pattern`, created on
+    /// first use.
+    pub(crate) fn host(&mut self, pattern: &str) -> &mut Router<State> {
+        let parsed = HostPattern::parse(pattern);
+        let index = match self.hosts.iter().position(|(p, _)| *p == parsed) {
+            Some(index) => index,
+            None => {
+                let mut router = Router::new();
+                router.host = Some(pattern.to_ascii_lowercase());
+                self.hosts.push((parsed, router));
+                self.hosts.len() - 1
+            }
+        };
+        &mut self.hosts[index].1
+    }
+
+    /// Give the route at `path` a `name` for reverse routing.
+    pub(crate) fn name(&mut self, name: &str, path: &str) {
+        let names = Arc::make_mut(&mut self.names);
+        assert!(
+            !names.contains_key(name),
+            "A route named `{}` is already registered",
+            name
+        );
+        names.insert(name.to_owned(), path.to_owned());
+    }
+
+    pub(crate) fn names(&self) -> &Arc<HashMap<String, String>> {
+        &self.names
+    }
+
+    /// Link the asset at `path` to its fingerprinted `url`, unless another
+    /// asset was registered at `path` first.
+    pub(crate) fn asset(&mut self, path: &str, url: &str) {
+        Arc::make_mut(&mut self.assets)
+            .entry(path.to_owned())
+            .or_insert_with(|| url.to_owned());
+    }
+
+    pub(crate) fn assets(&self) -> &Arc<HashMap<String, String>> {
+        &self.assets
+    }
+
+    /// Record a registered route, in registration order.
+    pub(crate) fn describe(&mut self, mut route: RouteInfo) {
+        if let Some(host) = &self.host {
+            route.set_host(host);
+        }
+        self.routes.push(route);
+    }
+
+    /// The registered routes, followed by the routes of each host.
+    pub(crate) fn routes(&self) -> impl Iterator<Item = &RouteInfo> {
+        self.routes.iter().chain(
+            self.hosts
+                .iter()
+                .flat_map(|(_, router)| router.routes.iter()),
+        )
+    }
+
+    pub(crate) fn set_not_found(&mut self, ep: Box<DynEndpoint<State>>) {
+        self.not_found = ep;
+    }
+
+    pub(crate) fn set_method_not_allowed(&mut self, ep: Box<DynEndpoint<State>>) {
+        self.method_not_allowed = ep;
+    }
+
+    /// Check that `path` is a valid route.
+    pub(crate) fn check(&self, path: &str) -> Result<(), RouteError> {
+        parse(path).map(|_| ())
+    }
+
+    /// Check that an endpoint for `method` at `path`, or for all methods if
+    /// `method` is `None`, could ever be selected. It can't when a route
+    /// registered earlier for the same method matches exactly the same paths
+    /// without constraints, since routes of the same shape are tried in
+    /// registration order.
+    pub(crate) fn check_endpoint(
+        &self,
+        path: &str,
+        method: Option<http_types::Method>,
+    ) -> Result<(), RouteError> {
+        let spec = parse(path)?;
+        for endpoint in &self.endpoints {
+            if endpoint.method != method || endpoint.constrained {
+                continue;
+            }
+            let existing = match endpoint.path.parse::<RouteSpec>() {
+                Ok(existing) => existing,
+                Err(_) => continue,
+            };
+            if same_shape(&spec, &existing) {
+                let name = self
+                    .names
+                    .iter()
+                    .find(|(_, named)| **named == endpoint.path)
+                    .map(|(name, _)| name.clone());
+                return Err(RouteError::Conflict {
+                    path: path.to_owned(),
+                    method,
+                    existing: endpoint.path.clone(),
+                    name,
+                });
+            }
         }
+        Ok(())
     }
 
     pub(crate) fn add(
@@ -42,57 +287,194 @@ impl<State: Clone + Send + Sync + 'static> Router<State> {
         path: &str,
         method: http_types::Method,
         ep: Box<DynEndpoint<State>>,
-    ) {
+        constraints: Vec<(String, Regex)>,
+    ) -> Result<(), RouteError> {
+        let constrained = !constraints.is_empty();
+        let entry = RouteEntry {
+            endpoint: ep,
+            constraints,
+        };
         self.method_map
             .entry(method)
             .or_insert_with(MethodRouter::new)
-            .add(path, ep)
-            .unwrap()
+            .add(path, entry)
+            .map_err(|message| invalid(path, message))?;
+        self.registered(path, Some(method), constrained);
+        Ok(())
     }
 
-    pub(crate) fn add_all(&mut self, path: &str, ep: Box<DynEndpoint<State>>) {
-        self.all_method_router.add(path, ep).unwrap()
+    pub(crate) fn add_all(
+        &mut self,
+        path: &str,
+        ep: Box<DynEndpoint<State>>,
+        constraints: Vec<(String, Regex)>,
+    ) -> Result<(), RouteError> {
+        let constrained = !constraints.is_empty();
+        let entry = RouteEntry {
+            endpoint: ep,
+            constraints,
+        };
+        self.all_method_router
+            .add(path, entry)
+            .map_err(|message| invalid(path, message))?;
+        self.registered(path, None, constrained);
+        Ok(())
     }
 
-    pub(crate) fn route(&self, path: &str, method: http_types::Method) -> Selection<'_, State> {
-        if let Some(m) = self
-            .method_map
-            .get(&method)
-            .and_then(|r| r.best_match(path))
-        {
+    fn registered(&mut self, path: &str, method: Option<http_types::Method>, constrained: bool) {
+        self.endpoints.push(Registered {
+            path: path.to_owned(),
+            method,
+            constrained,
+        });
+    }
+
+    /// Select the endpoint for a request. Requests to a host registered with
+    /// `host` are routed by that host's router, all others by this one.
+    pub(crate) fn route(
+        &self,
+        host: Option<&str>,
+        path: &str,
+        method: http_types::Method,
+    ) -> Selection<'_, State> {
+        if let Some(host) = host.map(strip_port) {
+            for (pattern, router) in &self.hosts {
+                if let Some(mut captures) = pattern.matches(host) {
+                    let mut selection = router.route_path(path, method, self);
+                    captures.append(selection.params);
+                    selection.params = captures;
+                    return selection;
+                }
+            }
+        }
+        self.route_path(path, method, self)
+    }
+
+    /// Select the endpoint for `path` and `method` in this router's own
+    /// tables, using the fallback endpoints of `fallback`.
+    fn route_path<'a>(
+        &'a self,
+        path: &str,
+        method: http_types::Method,
+        fallback: &'a Self,
+    ) -> Selection<'a, State> {
+        if let Some(m) = self.method_map.get(&method).and_then(|r| find(r, path)) {
             Selection {
-                endpoint: m.handler(),
+                endpoint: &*m.handler().endpoint,
                 params: m.captures().into_owned(),
+                allow: None,
+                names: &self.names,
+                assets: &self.assets,
             }
-        } else if let Some(m) = self.all_method_router.best_match(path) {
+        } else if let Some(m) = find(&self.all_method_router, path) {
             Selection {
-                endpoint: m.handler(),
+                endpoint: &*m.handler().endpoint,
                 params: m.captures().into_owned(),
+                allow: None,
+                names: &self.names,
+                assets: &self.assets,
+            }
+        } else if method == http_types::Method::Options {
+            // If no endpoint handles `OPTIONS` for this `path` but other methods are registered,
+            // answer on behalf of the path with the methods it supports
+            match self.allow_header(path) {
+                Some(allow) => Selection {
+                    endpoint: &options_endpoint,
+                    params: Captures::default(),
+                    allow: Some(allow),
+                    names: &self.names,
+                    assets: &self.assets,
+                },
+                None => Selection {
+                    endpoint: &*fallback.not_found,
+                    params: Captures::default(),
+                    allow: None,
+                    names: &self.names,
+                    assets: &self.assets,
+                },
             }
         } else if method == http_types::Method::Head {
             // If it is a HTTP HEAD request then check if there is a callback in the endpoints map
             // if not then fallback to the behavior of HTTP GET else proceed as usual
 
-            self.route(path, http_types::Method::Get)
-        } else if self
-            .method_map
-            .iter()
-            .filter(|(k, _)| **k != method)
-            .any(|(_, r)| r.best_match(path).is_some())
-        {
+            self.route_path(path, http_types::Method::Get, fallback)
+        } else if let Some(allow) = self.allow_header(path) {
             // If this `path` can be handled by a callback registered with a different HTTP method
             // should return 405 Method Not Allowed
             Selection {
-                endpoint: &method_not_allowed,
+                endpoint: &*fallback.method_not_allowed,
                 params: Captures::default(),
+                allow: Some(allow),
+                names: &self.names,
+                assets: &self.assets,
             }
         } else {
             Selection {
-                endpoint: &not_found_endpoint,
+                endpoint: &*fallback.not_found,
                 params: Captures::default(),
+                allow: None,
+                names: &self.names,
+                assets: &self.assets,
             }
         }
     }
+
+    /// The `Allow` header value for `path`, listing the registered methods in
+    /// a stable order, or `None` if no method is registered for it. `HEAD` is
+    /// included whenever `GET` is, since it falls back to the `GET` endpoint,
+    /// and `OPTIONS` is always included since it is answered automatically.
+    fn allow_header(&self, path: &str) -> Option<String> {
+        let mut allowed: Vec<_> = self
+            .method_map
+            .iter()
+            .filter(|(_, r)| find(r, path).is_some())
+            .map(|(method, _)| *method)
+            .collect();
+
+        if allowed.is_empty() {
+            return None;
+        }
+
+        if allowed.contains(&http_types::Method::Get)
+            && !allowed.contains(&http_types::Method::Head)
+        {
+            allowed.push(http_types::Method::Head);
+        }
+        if !allowed.contains(&http_types::Method::Options) {
+            allowed.push(http_types::Method::Options);
+        }
+
+        allowed.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        let allowed: Vec<_> = allowed.iter().map(|m| m.as_ref()).collect();
+        Some(allowed.join(", "))
+    }
+}
+
+fn parse(path: &str) -> Result<RouteSpec, RouteError> {
+    path.parse().map_err(|message| invalid(path, message))
+}
+
+fn invalid(path: &str, message: String) -> RouteError {
+    RouteError::Invalid {
+        path: path.to_owned(),
+        message,
+    }
+}
+
+/// Do `a` and `b` match exactly the same paths, ignoring parameter names?
+fn same_shape(a: &RouteSpec, b: &RouteSpec) -> bool {
+    a.segments().len() == b.segments().len()
+        && a.segments()
+            .iter()
+            .zip(b.segments())
+            .all(|pair| match pair {
+                (Segment::Slash, Segment::Slash)
+                | (Segment::Dot, Segment::Dot)
+                | (Segment::Param(_), Segment::Param(_))
+                | (Segment::Wildcard, Segment::Wildcard) => true,
+                (Segment::Exact(a), Segment::Exact(b)) => a == b,
+                _ => false,
+            })
 }
 
 async fn not_found_endpoint<State: Clone + Send + Sync + 'static>(
@@ -101,6 +483,12 @@ async fn not_found_endpoint<State: Clone + Send + Sync + 'static>(
     Ok(Response::new(StatusCode::NotFound))
 }
 
+async fn options_endpoint<State: Clone + Send + Sync + 'static>(
+    _req: Request<State>,
+) -> crate::Result {
+    Ok(Response::new(StatusCode::NoContent))
+}
+
 async fn method_not_allowed<State: Clone + Send + Sync + 'static>(
     _req: Request<State>,
 ) -> crate::Result {
@@ -5,11 +5,13 @@ use async_std::sync::Arc;
 
 #[cfg(feature = "cookies")]
 use crate::cookies;
-use crate::listener::{Listener, ToListener};
+use crate::http::headers;
+use crate::listener::{Listener, Shutdown, ToListener};
 use crate::log;
 use crate::middleware::{Middleware, Next};
-use crate::router::{Router, Selection};
-use crate::{Endpoint, Request, Route};
+use crate::request::BodyLimit;
+use crate::router::{AssetUrls, RouteNames, Router, Selection};
+use crate::{Endpoint, Request, Route, RouteError, RouteInfo};
 
 /// An HTTP server.
 ///
@@ -38,6 +40,11 @@ pub struct Server<State> {
     /// We don't use a Mutex around the Vec here because adding a middleware during execution should be an error.
     #[allow(clippy::rc_buffer)]
     middleware: Arc<Vec<Arc<dyn Middleware<State>>>>,
+    /// Middleware that runs before the request is routed.
+    #[allow(clippy::rc_buffer)]
+    pre_routing: Arc<Vec<Arc<dyn Middleware<State>>>>,
+    body_limit: Option<u64>,
+    shutdown: Shutdown,
 }
 
 impl Server<()> {
@@ -110,7 +117,10 @@ where
                 #[cfg(feature = "cookies")]
                 Arc::new(cookies::CookiesMiddleware::new()),
             ]),
+            pre_routing: Arc::new(Vec::new()),
+            body_limit: None,
             state,
+            shutdown: Shutdown::new(),
         }
     }
 
@@ -166,6 +176,166 @@ where
         Route::new(router, path.to_owned())
     }
 
+    /// Add a new route at the given `path`, like [`Server::at`], checking
+    /// the path first.
+    ///
+    /// Use [`Route::try_method`] and [`Route::try_all`] to also check that
+    /// the endpoints added to the route can be reached.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if `path` is not a valid route.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> Result<(), tide::RouteError> {
+    /// let mut app = tide::new();
+    /// app.try_at("/users/:id")?.get(|_| async { Ok("a user") });
+    ///
+    /// let err = app.try_at("/files/*name").err().unwrap();
+    /// assert!(err.to_string().starts_with("Invalid route `/files/*name`"));
+    /// # Ok(()) }
+    /// ```
+    pub fn try_at<'a>(&'a mut self, path: &str) -> Result<Route<'a, State>, RouteError> {
+        let router = Arc::get_mut(&mut self.router)
+            .expect("Registering routes is not possible after the Server has started");
+        router.check(path)?;
+        Ok(Route::new(router, path.to_owned()))
+    }
+
+    /// Add routes that only serve requests to the given host.
+    ///
+    /// Requests are dispatched on their host before their path: requests to
+    /// a host registered here are only routed to the routes added through
+    /// the returned [`Route`], while requests to any other host are routed
+    /// to the routes added with [`Server::at`]. The host is read as by
+    /// [`Request::host`] and compared without its port, ignoring case.
+    ///
+    /// A pattern of the form `*.example.com` matches any single subdomain
+    /// of `example.com`, which is available to endpoints as the `subdomain`
+    /// route parameter.
+    ///
+    /// The returned route is rooted at the host's `/`, so that paths can be
+    /// added with [`Route::at`] or a whole [`Server`] mounted with
+    /// [`Route::nest`]. Calling `host` again with the same pattern adds to the
+    /// same routes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` contains a `*` anywhere but as its leading label.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::Request;
+    ///
+    /// let mut api = tide::new();
+    /// api.at("/users").get(|_| async { Ok("users") });
+    ///
+    /// let mut app = tide::new();
+    /// app.host("api.example.com").nest(api);
+    /// app.host("*.example.com")
+    ///     .at("/")
+    ///     .get(|req: Request<()>| async move {
+    ///         Ok(format!("Welcome to {}", req.param("subdomain")?))
+    ///     });
+    /// app.at("/").get(|_| async { Ok("Unknown host") });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn host<'a>(&'a mut self, pattern: &str) -> Route<'a, State> {
+        let router = Arc::get_mut(&mut self.router)
+            .expect("Registering routes is not possible after the Server has started");
+        Route::new(router.host(pattern), String::new())
+    }
+
+    /// Set the endpoint used when no route matches the request path.
+    ///
+    /// By default Tide responds with an empty `404 Not Found`. Middleware
+    /// added with [`Server::with`] runs for this endpoint like for any other.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let mut app = tide::new();
+    /// use tide::{Response, StatusCode};
+    ///
+    /// app.not_found(|req: tide::Request<()>| async move {
+    ///     let mut res = Response::new(StatusCode::NotFound);
+    ///     res.set_body(format!("{} does not exist", req.url().path()));
+    ///     Ok(res)
+    /// });
+    /// ```
+    pub fn not_found(&mut self, ep: impl Endpoint<State>) -> &mut Self {
+        let router = Arc::get_mut(&mut self.router)
+            .expect("Registering routes is not possible after the Server has started");
+        router.set_not_found(Box::new(ep));
+        self
+    }
+
+    /// Set the endpoint used when the request path exists, but no endpoint
+    /// is registered for the request method.
+    ///
+    /// By default Tide responds with an empty `405 Method Not Allowed`. In
+    /// either case an `Allow` header listing the methods registered for the
+    /// path is added to the response, unless the endpoint already set one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let mut app = tide::new();
+    /// use tide::{Response, StatusCode};
+    ///
+    /// app.method_not_allowed(|req: tide::Request<()>| async move {
+    ///     let mut res = Response::new(StatusCode::MethodNotAllowed);
+    ///     res.set_body(format!("{} is not supported here", req.method()));
+    ///     Ok(res)
+    /// });
+    /// ```
+    pub fn method_not_allowed(&mut self, ep: impl Endpoint<State>) -> &mut Self {
+        let router = Arc::get_mut(&mut self.router)
+            .expect("Registering routes is not possible after the Server has started");
+        router.set_method_not_allowed(Box::new(ep));
+        self
+    }
+
+    /// List the routes registered on this server, in registration order.
+    ///
+    /// Routes of nested servers are listed right after the route they were
+    /// mounted on, with their paths prefixed accordingly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut app = tide::new();
+    /// app.at("/").get(|_| async { Ok("Hello, world!") });
+    /// app.at("/users/:id").put(|_| async { Ok("updated") });
+    ///
+    /// let routes: Vec<String> = app.routes().map(|route| route.to_string()).collect();
+    /// assert_eq!(routes, vec!["GET /", "PUT /users/:id"]);
+    /// ```
+    pub fn routes(&self) -> impl Iterator<Item = &RouteInfo> {
+        self.router.routes()
+    }
+
+    /// The routing table of this server.
+    pub(crate) fn router(&self) -> &Router<State> {
+        &self.router
+    }
+
+    /// The names of the server-level middleware, in the order they run.
+    pub(crate) fn middleware_names(&self) -> Vec<String> {
+        self.middleware
+            .iter()
+            .map(|m| m.name().to_owned())
+            .collect()
+    }
+
     /// Add middleware to an application.
     ///
     /// Middleware provides customization of the request/response cycle, such as compression,
@@ -186,6 +356,91 @@ where
         self
     }
 
+    /// Add middleware that runs before the request is routed.
+    ///
+    /// Pre-routing middleware runs before the endpoint is selected, so
+    /// changes it makes to the request URL or method decide which route
+    /// handles the request. This is useful to rewrite legacy URLs, strip a
+    /// locale prefix, or override the method of a form submission. Route
+    /// parameters are not available yet when it runs.
+    ///
+    /// Pre-routing middleware is processed in the order in which it is
+    /// applied, before any middleware added with [`Server::with`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use std::future::Future;
+    /// use std::pin::Pin;
+    /// use tide::{Next, Request};
+    ///
+    /// fn rewrite<'a>(
+    ///     mut req: Request<()>,
+    ///     next: Next<'a, ()>,
+    /// ) -> Pin<Box<dyn Future<Output = tide::Result> + Send + 'a>> {
+    ///     if req.url().path() == "/old-home" {
+    ///         req.url_mut().set_path("/");
+    ///     }
+    ///     Box::pin(async move { Ok(next.run(req).await) })
+    /// }
+    ///
+    /// let mut app = tide::new();
+    /// app.with_pre_routing(rewrite);
+    /// app.at("/").get(|_| async { Ok("Home") });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn with_pre_routing<M>(&mut self, middleware: M) -> &mut Self
+    where
+        M: Middleware<State>,
+    {
+        log::trace!("Adding pre-routing middleware {}", middleware.name());
+        let m = Arc::get_mut(&mut self.pre_routing)
+            .expect("Registering middleware is not possible after the Server has started");
+        m.push(Arc::new(middleware));
+        self
+    }
+
+    /// Set the maximum size in bytes of request bodies read through
+    /// [`Request::body_bytes`], [`Request::body_string`],
+    /// [`Request::body_json`] and [`Request::body_form`].
+    ///
+    /// Reading a larger body fails with a `413 Payload Too Large` error,
+    /// without reading the body at all if its `Content-Length` already
+    /// exceeds the limit. Routes can set a different limit with
+    /// [`Route::body_limit`]. There is no limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task::block_on;
+    /// # fn main() -> Result<(), std::io::Error> { block_on(async {
+    /// #
+    /// use tide::Request;
+    ///
+    /// let mut app = tide::new();
+    /// app.body_limit(64 * 1024);
+    /// app.at("/echo").post(|mut req: Request<()>| async move {
+    ///     Ok(req.body_string().await?)
+    /// });
+    /// app.at("/upload")
+    ///     .body_limit(16 * 1024 * 1024)
+    ///     .post(|mut req: Request<()>| async move {
+    ///         Ok(format!("{} bytes", req.body_bytes().await?.len()))
+    ///     });
+    /// app.listen("127.0.0.1:8080").await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn body_limit(&mut self, limit: u64) -> &mut Self {
+        self.body_limit = Some(limit);
+        self
+    }
+
     /// Asynchronously serve the app with the supplied listener.
     ///
     /// This is a shorthand for calling `Server::bind`, logging the `ListenInfo`
@@ -213,6 +468,39 @@ where
         Ok(())
     }
 
+    /// Get a handle that can be used to gracefully shut down the server.
+    ///
+    /// Triggering the handle stops every listener this server is bound to
+    /// from accepting new connections, and makes `Server::listen` (or
+    /// `Listener::accept`) return once in-flight requests have completed or
+    /// the grace period has elapsed. See [`Shutdown`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use async_std::task;
+    /// # fn main() -> Result<(), std::io::Error> { task::block_on(async {
+    /// #
+    /// use std::time::Duration;
+    /// use tide::prelude::*;
+    ///
+    /// let mut app = tide::new();
+    /// app.at("/").get(|_| async { Ok("Hello, world!") });
+    ///
+    /// let shutdown = app.shutdown_handle();
+    /// let mut listener = app.bind("127.0.0.1:8080").await?;
+    /// task::spawn(async move {
+    ///     task::sleep(Duration::from_secs(60)).await;
+    ///     shutdown.trigger(Duration::from_secs(10));
+    /// });
+    /// listener.accept().await?;
+    /// #
+    /// # Ok(()) }) }
+    /// ```
+    pub fn shutdown_handle(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
     /// Asynchronously bind the listener.
     ///
     /// Bind the listener. This starts the listening process by opening the
@@ -284,16 +572,19 @@ where
             router,
             state,
             middleware,
+            pre_routing,
+            ..
         } = self.clone();
 
-        let method = req.method().to_owned();
-        let Selection { endpoint, params } = router.route(req.url().path(), method);
-        let route_params = vec![params];
-        let req = Request::new(state, req, route_params);
-
+        let req = Request::new(state, req, Vec::new());
+        let routing = Routing {
+            router,
+            middleware,
+            body_limit: self.body_limit,
+        };
         let next = Next {
-            endpoint,
-            next_middleware: &middleware,
+            endpoint: &routing,
+            next_middleware: &pre_routing,
         };
 
         let res = next.run(req).await;
@@ -329,6 +620,9 @@ impl<State: Clone> Clone for Server<State> {
             router: self.router.clone(),
             state: self.state.clone(),
             middleware: self.middleware.clone(),
+            pre_routing: self.pre_routing.clone(),
+            body_limit: self.body_limit,
+            shutdown: self.shutdown.clone(),
         }
     }
 }
@@ -339,26 +633,105 @@ impl<State: Clone + Sync + Send + 'static, InnerState: Clone + Sync + Send + 'st
 {
     async fn call(&self, req: Request<State>) -> crate::Result {
         let Request {
-            req,
-            mut route_params,
-            ..
+            req, route_params, ..
         } = req;
+        let req = Request::new(self.state.clone(), req, route_params);
+        let routing = Routing {
+            router: self.router.clone(),
+            middleware: self.middleware.clone(),
+            body_limit: self.body_limit,
+        };
+        let next = Next {
+            endpoint: &routing,
+            next_middleware: &self.pre_routing,
+        };
+        Ok(next.run(req).await)
+    }
+}
+
+/// The routes of a sub-app whose state is projected from the state of the
+/// outer server on every request. Created by `Route::nest_with`.
+pub(crate) struct Projected<InnerState, F> {
+    router: Arc<Router<InnerState>>,
+    project: F,
+}
+
+impl<InnerState, F> Projected<InnerState, F> {
+    pub(crate) fn new(router: Arc<Router<InnerState>>, project: F) -> Self {
+        Self { router, project }
+    }
+}
+
+#[async_trait::async_trait]
+impl<State, InnerState, F> Endpoint<State> for Projected<InnerState, F>
+where
+    State: Clone + Send + Sync + 'static,
+    InnerState: Clone + Send + Sync + 'static,
+    F: Fn(&State) -> InnerState + Send + Sync + 'static,
+{
+    async fn call(&self, req: Request<State>) -> crate::Result {
+        let state = (self.project)(req.state());
+        let Request {
+            req, route_params, ..
+        } = req;
+        let routing = Routing {
+            router: self.router.clone(),
+            middleware: Arc::default(),
+            body_limit: None,
+        };
+        routing.call(Request::new(state, req, route_params)).await
+    }
+}
+
+/// The endpoint at the end of the pre-routing middleware chain, which routes
+/// the request and runs it through the server middleware and the selected
+/// endpoint.
+struct Routing<State> {
+    router: Arc<Router<State>>,
+    #[allow(clippy::rc_buffer)]
+    middleware: Arc<Vec<Arc<dyn Middleware<State>>>>,
+    body_limit: Option<u64>,
+}
+
+#[async_trait::async_trait]
+impl<State: Clone + Send + Sync + 'static> Endpoint<State> for Routing<State> {
+    async fn call(&self, mut req: Request<State>) -> crate::Result {
+        if let Some(limit) = self.body_limit {
+            req.set_ext(BodyLimit(limit));
+        }
+
         let path = req.url().path().to_owned();
-        let method = req.method().to_owned();
-        let router = self.router.clone();
-        let middleware = self.middleware.clone();
-        let state = self.state.clone();
+        let method = req.method();
+        let Selection {
+            endpoint,
+            params,
+            allow,
+            names,
+            assets,
+        } = self.router.route(req.host(), &path, method);
 
-        let Selection { endpoint, params } = router.route(&path, method);
-        route_params.push(params);
-        let req = Request::new(state, req, route_params);
+        // When nested, the outer server already registered the route names
+        // of this one, prefixed with the path this server is mounted at.
+        if req.ext::<RouteNames>().is_none() {
+            req.set_ext(RouteNames(names.clone()));
+        }
+        if req.ext::<AssetUrls>().is_none() {
+            req.set_ext(AssetUrls(assets.clone()));
+        }
+        req.route_params.push(params);
 
         let next = Next {
             endpoint,
-            next_middleware: &middleware,
+            next_middleware: &self.middleware,
         };
 
-        Ok(next.run(req).await)
+        let mut res = next.run(req).await;
+        if let Some(allow) = allow {
+            if res.header(headers::ALLOW).is_none() {
+                res.insert_header(headers::ALLOW, allow);
+            }
+        }
+        Ok(res)
     }
 }
 
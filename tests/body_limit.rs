@@ -0,0 +1,80 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use async_std::io::Cursor;
+use tide::{Body, Request, StatusCode};
+
+fn app() -> tide::Server<()> {
+    let mut app = tide::new();
+    app.body_limit(8);
+    app.at("/echo")
+        .post(|mut req: Request<()>| async move { req.body_string().await });
+    app.at("/upload")
+        .body_limit(32)
+        .post(|mut req: Request<()>| async move { Ok(req.body_bytes().await?.len().to_string()) });
+    app.at("/ignore")
+        .body_limit(4)
+        .post(|_| async { Ok("ignored") });
+    app
+}
+
+/// A body of unknown length, as sent with chunked encoding.
+fn streamed(body: &str) -> Body {
+    Body::from_reader(Cursor::new(body.as_bytes().to_vec()), None)
+}
+
+#[async_std::test]
+async fn bodies_within_the_limit_are_read() -> tide::Result<()> {
+    let app = app();
+    let res = app.post("/echo").body("12345678").recv_string().await?;
+    assert_eq!(res, "12345678");
+    let res = app
+        .post("/echo")
+        .body(streamed("1234"))
+        .recv_string()
+        .await?;
+    assert_eq!(res, "1234");
+    let res = app
+        .post("/upload")
+        .body("x".repeat(32))
+        .recv_string()
+        .await?;
+    assert_eq!(res, "32");
+    Ok(())
+}
+
+#[async_std::test]
+async fn declared_length_over_the_limit_is_rejected() -> tide::Result<()> {
+    let app = app();
+    let res = app.post("/echo").body("123456789").await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    let res = app.post("/upload").body("x".repeat(33)).await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    // Route limits reject the request even if the endpoint ignores the body.
+    let res = app.post("/ignore").body("12345").await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    Ok(())
+}
+
+#[async_std::test]
+async fn streamed_body_over_the_limit_is_rejected() -> tide::Result<()> {
+    let app = app();
+    let res = app.post("/echo").body(streamed("123456789")).await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    let res = app.post("/upload").body(streamed(&"x".repeat(33))).await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    Ok(())
+}
+
+#[test]
+fn route_limits_are_not_listed_as_middleware() {
+    let app = app();
+    let upload = app
+        .routes()
+        .find(|route| route.path() == "/upload")
+        .unwrap();
+    assert!(upload
+        .middleware()
+        .iter()
+        .all(|name| !name.contains("BodyLimit")));
+}
@@ -0,0 +1,250 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use async_compression::futures::bufread::{
+    BrotliDecoder, BrotliEncoder, GzipDecoder, GzipEncoder, ZlibDecoder,
+};
+use async_std::io::{self, prelude::*};
+use tide::compression::{CompressMiddleware, DecompressMiddleware, Encoding};
+use tide::http::{headers, mime};
+use tide::{Body, Request, Response, StatusCode};
+
+const TEXT: &str = "The quick brown fox jumps over the lazy dog. ";
+
+fn app() -> tide::Server<()> {
+    let mut app = tide::new();
+    app.with(CompressMiddleware::new());
+    app.at("/text").get(|_| async { Ok(TEXT.repeat(100)) });
+    app.at("/small").get(|_| async { Ok(TEXT) });
+    app.at("/stream").get(|_| async {
+        let reader = io::Cursor::new(TEXT.repeat(100).into_bytes());
+        let mut body = Body::from_reader(io::BufReader::new(reader), None);
+        body.set_mime(mime::JSON);
+        Ok(body)
+    });
+    app.at("/image").get(|_| async {
+        let mut res = Response::new(200);
+        res.set_body(vec![0; 2048]);
+        res.set_content_type(mime::PNG);
+        Ok(res)
+    });
+    app.at("/events").get(|_| async {
+        let mut res = Response::new(200);
+        res.set_body(TEXT.repeat(100));
+        res.set_content_type(mime::SSE);
+        Ok(res)
+    });
+    app.at("/encoded").get(|_| async {
+        let mut res = Response::new(200);
+        res.set_body(TEXT.repeat(100));
+        res.insert_header(headers::CONTENT_ENCODING, "identity");
+        Ok(res)
+    });
+    app
+}
+
+async fn decode(encoding: &str, body: Vec<u8>) -> io::Result<String> {
+    let mut decoded = String::new();
+    match encoding {
+        "br" => {
+            BrotliDecoder::new(&body[..])
+                .read_to_string(&mut decoded)
+                .await?
+        }
+        "gzip" => {
+            GzipDecoder::new(&body[..])
+                .read_to_string(&mut decoded)
+                .await?
+        }
+        "deflate" => {
+            ZlibDecoder::new(&body[..])
+                .read_to_string(&mut decoded)
+                .await?
+        }
+        _ => panic!("unexpected encoding {}", encoding),
+    };
+    Ok(decoded)
+}
+
+#[async_std::test]
+async fn compresses_with_the_preferred_encoding() -> tide::Result<()> {
+    let app = app();
+    for (accept, encoding) in [
+        ("gzip", "gzip"),
+        ("deflate, gzip;q=0.5", "deflate"),
+        ("gzip, deflate, br", "br"),
+        ("br;q=0, *", "gzip"),
+    ] {
+        let mut res = app.get("/text").header("Accept-Encoding", accept).await?;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res[headers::CONTENT_ENCODING], encoding);
+        assert_eq!(res[headers::VARY], "Accept-Encoding");
+        assert!(res.header(headers::CONTENT_LENGTH).is_none());
+        assert_eq!(res.content_type(), Some(mime::PLAIN));
+        let body = res.body_bytes().await?;
+        assert!(body.len() < TEXT.len() * 100);
+        assert_eq!(decode(encoding, body).await?, TEXT.repeat(100));
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn compresses_streaming_bodies() -> tide::Result<()> {
+    let app = app();
+    let mut res = app.get("/stream").header("Accept-Encoding", "gzip").await?;
+    assert_eq!(res[headers::CONTENT_ENCODING], "gzip");
+    assert_eq!(res.content_type(), Some(mime::JSON));
+    let body = res.body_bytes().await?;
+    assert_eq!(decode("gzip", body).await?, TEXT.repeat(100));
+    Ok(())
+}
+
+#[async_std::test]
+async fn leaves_unacceptable_responses_uncompressed() -> tide::Result<()> {
+    let app = app();
+
+    let mut res = app.get("/text").await?;
+    assert!(res.header(headers::CONTENT_ENCODING).is_none());
+    assert_eq!(res[headers::VARY], "Accept-Encoding");
+    assert_eq!(res.body_string().await?, TEXT.repeat(100));
+
+    let res = app
+        .get("/text")
+        .header("Accept-Encoding", "identity, gzip;q=0")
+        .await?;
+    assert!(res.header(headers::CONTENT_ENCODING).is_none());
+
+    for path in ["/small", "/image", "/events", "/encoded"] {
+        let res = app.get(path).header("Accept-Encoding", "gzip").await?;
+        assert!(res.header(headers::VARY).is_none(), "{}", path);
+        let encoding = res.header(headers::CONTENT_ENCODING);
+        assert!(
+            encoding.is_none_or(|encoding| encoding == "identity"),
+            "{}",
+            path
+        );
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn only_uses_configured_encodings() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.with(
+        CompressMiddleware::new()
+            .encodings(vec![Encoding::Gzip])
+            .min_size(0),
+    );
+    app.at("/").get(|_| async { Ok(TEXT) });
+
+    let res = app.get("/").header("Accept-Encoding", "br").await?;
+    assert!(res.header(headers::CONTENT_ENCODING).is_none());
+    let mut res = app
+        .get("/")
+        .header("Accept-Encoding", "br, gzip;q=0.1")
+        .await?;
+    assert_eq!(res[headers::CONTENT_ENCODING], "gzip");
+    let body = res.body_bytes().await?;
+    assert_eq!(decode("gzip", body).await?, TEXT);
+    Ok(())
+}
+
+async fn gzip(body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoded = Vec::new();
+    GzipEncoder::new(body).read_to_end(&mut encoded).await?;
+    Ok(encoded)
+}
+
+fn decompressing_app() -> tide::Server<()> {
+    let mut app = tide::new();
+    app.with(DecompressMiddleware::new().max_size(4096));
+    app.at("/").post(|mut req: Request<()>| async move {
+        assert!(req.header(headers::CONTENT_ENCODING).is_none());
+        assert!(req.header(headers::CONTENT_LENGTH).is_none());
+        req.body_string().await
+    });
+    app
+}
+
+#[async_std::test]
+async fn decompresses_request_bodies() -> tide::Result<()> {
+    let app = decompressing_app();
+
+    let res = app
+        .post("/")
+        .header("Content-Encoding", "gzip")
+        .body(gzip(TEXT.as_bytes()).await?)
+        .recv_string()
+        .await?;
+    assert_eq!(res, TEXT);
+
+    // Codings applied in turn are undone in reverse.
+    let mut encoded = Vec::new();
+    BrotliEncoder::new(&gzip(TEXT.as_bytes()).await?[..])
+        .read_to_end(&mut encoded)
+        .await?;
+    let res = app
+        .post("/")
+        .header("Content-Encoding", "gzip, identity, br")
+        .body(encoded)
+        .recv_string()
+        .await?;
+    assert_eq!(res, TEXT);
+
+    let res = app.post("/").body(TEXT).recv_string().await?;
+    assert_eq!(res, TEXT);
+    Ok(())
+}
+
+#[async_std::test]
+async fn decompresses_zlib_deflate_bodies() -> tide::Result<()> {
+    // `Hello, deflate!` as compressed by zlib, with its header and checksum.
+    let encoded: &[u8] = &[
+        120, 156, 243, 72, 205, 201, 201, 215, 81, 72, 73, 77, 203, 73, 44, 73, 85, 4, 0, 42, 36,
+        5, 55,
+    ];
+    let res = decompressing_app()
+        .post("/")
+        .header("Content-Encoding", "deflate")
+        .body(encoded)
+        .recv_string()
+        .await?;
+    assert_eq!(res, "Hello, deflate!");
+    Ok(())
+}
+
+#[async_std::test]
+async fn rejects_bad_request_encodings() -> tide::Result<()> {
+    let app = decompressing_app();
+
+    let res = app
+        .post("/")
+        .header("Content-Encoding", "zstd")
+        .body(TEXT)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UnsupportedMediaType);
+    assert_eq!(res[headers::ACCEPT_ENCODING], "br, deflate, gzip");
+
+    let res = app
+        .post("/")
+        .header("Content-Encoding", "gzip")
+        .body(TEXT)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UnprocessableEntity);
+    Ok(())
+}
+
+#[async_std::test]
+async fn limits_decompressed_size() -> tide::Result<()> {
+    let app = decompressing_app();
+    let bomb = gzip(&[0; 100_000]).await?;
+    assert!(bomb.len() < 4096);
+
+    let res = app
+        .post("/")
+        .header("Content-Encoding", "gzip")
+        .body(bomb)
+        .await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    Ok(())
+}
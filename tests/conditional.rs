@@ -0,0 +1,133 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use std::time::{Duration, SystemTime};
+use tide::conditional::ConditionalMiddleware;
+use tide::http::conditional::{ETag, LastModified};
+use tide::http::headers;
+use tide::{Response, StatusCode};
+
+fn app() -> tide::Server<()> {
+    let mut app = tide::new();
+    app.with(ConditionalMiddleware::new());
+    app.at("/hello").get(|_| async { Ok("Hello, world!") });
+    app.at("/tagged")
+        .get(|_| async {
+            let mut res = Response::new(200);
+            ETag::new_weak("v1".into()).apply(&mut res);
+            res.insert_header(headers::CACHE_CONTROL, "max-age=60");
+            res.set_body("version one");
+            Ok(res)
+        })
+        .post(|_| async {
+            let mut res = Response::new(200);
+            ETag::new("v1".into()).apply(&mut res);
+            Ok(res)
+        });
+    app.at("/dated").get(|_| async {
+        let mut res = Response::new(200);
+        LastModified::new(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+            .apply(&mut res);
+        res.set_body("old news");
+        Ok(res)
+    });
+    app
+}
+
+#[async_std::test]
+async fn computes_etags_for_buffered_bodies() -> tide::Result<()> {
+    let app = app();
+    let res = app.get("/hello").await?;
+    let etag = res[headers::ETAG].as_str().to_owned();
+    assert!(etag.starts_with('"'));
+
+    let res = app.get("/hello").header("If-None-Match", &*etag).await?;
+    assert_eq!(res.status(), StatusCode::NotModified);
+    assert_eq!(res[headers::ETAG], etag.as_str());
+    assert!(res.header(headers::CONTENT_TYPE).is_none());
+
+    let mut res = app
+        .get("/hello")
+        .header("If-None-Match", "\"other\"")
+        .await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    assert_eq!(res.body_string().await?, "Hello, world!");
+    Ok(())
+}
+
+#[async_std::test]
+async fn uses_endpoint_etags() -> tide::Result<()> {
+    let app = app();
+    for if_none_match in ["W/\"v1\"", "\"v0\", \"v1\"", "*"] {
+        let res = app
+            .get("/tagged")
+            .header("If-None-Match", if_none_match)
+            .await?;
+        assert_eq!(res.status(), StatusCode::NotModified, "{}", if_none_match);
+        assert_eq!(res[headers::ETAG], "W/\"v1\"");
+        assert_eq!(res[headers::CACHE_CONTROL], "max-age=60");
+        assert_eq!(res.len(), Some(0));
+    }
+
+    // Only GET and HEAD requests are conditional.
+    let res = app
+        .post("/tagged")
+        .header("If-None-Match", "\"v1\"")
+        .await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    Ok(())
+}
+
+#[async_std::test]
+async fn compares_modification_dates() -> tide::Result<()> {
+    let app = app();
+    let res = app
+        .get("/dated")
+        .header("If-Modified-Since", "Sun, 09 Sep 2001 01:46:40 GMT")
+        .await?;
+    assert_eq!(res.status(), StatusCode::NotModified);
+    assert_eq!(res[headers::LAST_MODIFIED], "Sun, 09 Sep 2001 01:46:40 GMT");
+
+    let res = app
+        .get("/dated")
+        .header("If-Modified-Since", "Sun, 09 Sep 2001 01:46:39 GMT")
+        .await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+
+    // If-None-Match takes precedence over If-Modified-Since.
+    let res = app
+        .get("/dated")
+        .header("If-None-Match", "\"other\"")
+        .header("If-Modified-Since", "Sun, 09 Sep 2001 01:46:40 GMT")
+        .await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    Ok(())
+}
+
+#[async_std::test]
+async fn serves_unmodified_files() -> tide::Result<()> {
+    let dir = tempfile::tempdir()?;
+    std::fs::write(dir.path().join("index.html"), "<h1>Hello</h1>")?;
+
+    let mut app = tide::new();
+    app.with(ConditionalMiddleware::new().max_size(0));
+    app.at("/static/*").serve_dir(dir.path())?;
+    app.at("/index.html")
+        .serve_file(dir.path().join("index.html"))?;
+
+    for path in ["/static/index.html", "/index.html"] {
+        let res = app.get(path).await?;
+        assert_eq!(res.status(), StatusCode::Ok);
+        let etag = res[headers::ETAG].as_str().to_owned();
+        let last_modified = res[headers::LAST_MODIFIED].as_str().to_owned();
+
+        let res = app.get(path).header("If-None-Match", &*etag).await?;
+        assert_eq!(res.status(), StatusCode::NotModified);
+        let res = app
+            .get(path)
+            .header("If-Modified-Since", &*last_modified)
+            .await?;
+        assert_eq!(res.status(), StatusCode::NotModified);
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use tide::http::Method;
+use tide::{Request, RouteError};
+
+#[async_std::test]
+async fn rejected_params_fall_through() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/users/:id")
+        .constrain("id", r"\d+")
+        .get(|req: Request<()>| async move { Ok(format!("id {}", req.param("id")?)) });
+    app.at("/users/:name")
+        .get(|req: Request<()>| async move { Ok(format!("name {}", req.param("name")?)) });
+    app.at("/posts/:slug")
+        .constrain("slug", "[a-z ]+")
+        .get(|req: Request<()>| async move { req.param_as::<String>("slug") });
+
+    assert_eq!(app.get("/users/42").recv_string().await?, "id 42");
+    assert_eq!(app.get("/users/nori").recv_string().await?, "name nori");
+    // The pattern must match the whole parameter.
+    assert_eq!(app.get("/users/42a").recv_string().await?, "name 42a");
+
+    // Constraints are checked against the decoded parameter.
+    assert_eq!(
+        app.get("/posts/hello%20world").recv_string().await?,
+        "hello world"
+    );
+    assert_eq!(app.get("/posts/Hello").await?.status(), 404);
+    Ok(())
+}
+
+#[async_std::test]
+async fn constraints_apply_to_subroutes() -> tide::Result<()> {
+    let mut app = tide::new();
+    let mut user = app.at("/users/:id");
+    user.constrain("id", r"\d+");
+    user.at("posts").get(|_| async { Ok("posts") });
+
+    assert_eq!(app.get("/users/1/posts").recv_string().await?, "posts");
+    assert_eq!(app.get("/users/nori/posts").await?.status(), 404);
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "Route `/users/:id` has no param `name` to constrain")]
+fn unknown_param_panics() {
+    let mut app = tide::new();
+    app.at("/users/:id").constrain("name", ".*");
+}
+
+#[test]
+fn try_method_reports_unreachable_endpoints() -> Result<(), RouteError> {
+    let mut app = tide::new();
+    app.at("/users/:id").name("user").get(|_| async { Ok("") });
+    app.at("/posts/:id").all(|_| async { Ok("") });
+
+    let err = app
+        .try_at("/users/:user_id")?
+        .try_method(Method::Get, |_| async { Ok("") })
+        .err()
+        .unwrap();
+    assert_eq!(
+        err,
+        RouteError::Conflict {
+            path: "/users/:user_id".into(),
+            method: Some(Method::Get),
+            existing: "/users/:id".into(),
+            name: Some("user".into()),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "Route `GET /users/:user_id` is unreachable: \
+         route `user` (`GET /users/:id`) matches the same requests first"
+    );
+
+    // The same path and method registered twice.
+    let err = app
+        .try_at("/posts/:id")?
+        .try_all(|_| async { Ok("") })
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Route `* /posts/:id` is unreachable: `* /posts/:id` matches the same requests first"
+    );
+
+    app.try_at("/users/new")?
+        .try_method(Method::Get, |_| async { Ok("") })?;
+    app.try_at("/users/:id/posts")?
+        .try_method(Method::Get, |_| async { Ok("") })?;
+    app.try_at("/posts/:id")?
+        .try_method(Method::Get, |_| async { Ok("") })?;
+    Ok(())
+}
+
+#[test]
+fn different_methods_dont_conflict() -> Result<(), RouteError> {
+    let mut app = tide::new();
+    app.at("/users/:id").get(|_| async { Ok("") });
+    app.try_at("/users/:user_id")?
+        .try_method(Method::Post, |_| async { Ok("") })?
+        .try_method(Method::Put, |_| async { Ok("") })?
+        .try_all(|_| async { Ok("") })?;
+    Ok(())
+}
+
+#[async_std::test]
+async fn constrained_routes_dont_conflict() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.try_at("/users/:id")?
+        .constrain("id", r"\d+")
+        .try_method(Method::Get, |_| async { Ok("id") })?;
+    app.try_at("/users/:name")?
+        .try_method(Method::Get, |_| async { Ok("name") })?;
+    assert!(app
+        .try_at("/users/:login")?
+        .try_method(Method::Get, |_| async { Ok("login") })
+        .is_err());
+
+    assert_eq!(app.get("/users/42").recv_string().await?, "id");
+    assert_eq!(app.get("/users/nori").recv_string().await?, "name");
+    Ok(())
+}
+
+#[test]
+fn host_routes_report_unreachable_endpoints() -> Result<(), RouteError> {
+    let mut app = tide::new();
+    app.at("/users/:id").get(|_| async { Ok("") });
+    app.host("api.example.com")
+        .at("/users/:id")
+        .get(|_| async { Ok("") });
+
+    let mut api = app.host("api.example.com");
+    let err = api
+        .at("/users/:name")
+        .try_method(Method::Get, |_| async { Ok("") })
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "Route `GET /users/:name` is unreachable: `GET /users/:id` matches the same requests first"
+    );
+    app.host("www.example.com")
+        .at("/users/:name")
+        .try_method(Method::Get, |_| async { Ok("") })?;
+    Ok(())
+}
+
+#[test]
+fn try_at_reports_invalid_paths() {
+    let mut app = tide::new();
+    let err = app.try_at("/files/*name").err().unwrap();
+    assert!(matches!(err, RouteError::Invalid { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("Invalid route `/files/*name`: "));
+}
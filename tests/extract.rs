@@ -0,0 +1,129 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use serde::Deserialize;
+use tide::extract::{handler, Form, Header, Json, Path, Query, State};
+use tide::http::{auth::Authorization, mime};
+use tide::{Body, Request, StatusCode};
+
+#[derive(Deserialize)]
+struct User {
+    name: String,
+    age: u8,
+}
+
+#[derive(Deserialize)]
+struct Id {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct Page {
+    page: u32,
+}
+
+async fn update(
+    Path(Id { id }): Path<Id>,
+    Query(Page { page }): Query<Page>,
+    Json(user): Json<User>,
+    State(greeting): State<&'static str>,
+) -> tide::Result<String> {
+    Ok(format!(
+        "{} {}, #{} aged {} on page {}",
+        greeting, user.name, id, user.age, page
+    ))
+}
+
+fn app() -> tide::Server<&'static str> {
+    let mut app = tide::with_state("Hello");
+    app.at("/users/:id").put(handler(update));
+    app.at("/form")
+        .post(handler(|Form(user): Form<User>| async move {
+            Ok(format!("{} {}", user.name, user.age))
+        }));
+    app.at("/auth").get(handler(
+        |Header(auth): Header<Authorization>, State(_): State<&'static str>| async move {
+            Ok(auth.credentials().to_owned())
+        },
+    ));
+    app.at("/maybe-auth")
+        .get(handler(|auth: Option<Header<Authorization>>| async move {
+            Ok(auth.is_some().to_string())
+        }));
+    // Endpoints taking a `Request` keep working next to extractor endpoints.
+    app.at("/plain")
+        .get(|req: Request<&'static str>| async move { Ok(req.state().to_string()) });
+    app
+}
+
+#[async_std::test]
+async fn extracts_arguments() -> tide::Result<()> {
+    let app = app();
+    let body = Body::from_json(&serde_json::json!({ "name": "nori", "age": 4 }))?;
+    let res = app.put("/users/7?page=2").body(body).recv_string().await?;
+    assert_eq!(res, "Hello nori, #7 aged 4 on page 2");
+
+    let res = app
+        .post("/form")
+        .body(Body::from_string("name=chashu&age=3".into()))
+        .content_type(mime::FORM)
+        .recv_string()
+        .await?;
+    assert_eq!(res, "chashu 3");
+
+    let res = app
+        .get("/auth")
+        .header("Authorization", "Bearer token")
+        .recv_string()
+        .await?;
+    assert_eq!(res, "token");
+
+    assert_eq!(app.get("/maybe-auth").recv_string().await?, "false");
+    assert_eq!(app.get("/plain").recv_string().await?, "Hello");
+    Ok(())
+}
+
+#[async_std::test]
+async fn rejects_invalid_requests() -> tide::Result<()> {
+    let app = app();
+    let json = |value: serde_json::Value| Body::from_json(&value).unwrap();
+
+    let res = app
+        .put("/users/7?page=2")
+        .body(Body::from_string("{}".into()))
+        .content_type(mime::PLAIN)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UnsupportedMediaType);
+
+    let res = app
+        .put("/users/7?page=2")
+        .body(Body::from_string("{".into()))
+        .content_type(mime::JSON)
+        .await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+
+    let res = app
+        .put("/users/7?page=2")
+        .body(json(serde_json::json!({ "name": "nori" })))
+        .await?;
+    assert_eq!(res.status(), StatusCode::UnprocessableEntity);
+
+    let body = json(serde_json::json!({ "name": "nori", "age": 4 }));
+    let res = app.put("/users/nori?page=2").body(body).await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+
+    let body = json(serde_json::json!({ "name": "nori", "age": 4 }));
+    let res = app.put("/users/7").body(body).await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+
+    let res = app
+        .post("/form")
+        .body(Body::from_string("name=chashu".into()))
+        .content_type(mime::FORM)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UnprocessableEntity);
+
+    let res = app.get("/auth").await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+    Ok(())
+}
@@ -0,0 +1,120 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+use tide::http::headers::ALLOW;
+use tide::{Request, Response, StatusCode};
+
+#[async_std::test]
+async fn default_not_found() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { Ok("root") });
+
+    let mut res = app.get("/missing").await?;
+    assert_eq!(res.status(), StatusCode::NotFound);
+    assert!(res.header(ALLOW).is_none());
+    assert_eq!(res.body_string().await?, "");
+    Ok(())
+}
+
+#[async_std::test]
+async fn default_method_not_allowed_sets_allow() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/users").get(|_| async { Ok("list") });
+    app.at("/users").post(|_| async { Ok("create") });
+    app.at("/users/:id").delete(|_| async { Ok("delete") });
+
+    let res = app.put("/users").await?;
+    assert_eq!(res.status(), StatusCode::MethodNotAllowed);
+    assert_eq!(res[ALLOW], "GET, HEAD, OPTIONS, POST");
+
+    let res = app.get("/users/1").await?;
+    assert_eq!(res.status(), StatusCode::MethodNotAllowed);
+    assert_eq!(res[ALLOW], "DELETE, OPTIONS");
+    Ok(())
+}
+
+#[async_std::test]
+async fn custom_not_found() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { Ok("root") });
+    app.not_found(|req: Request<()>| async move {
+        let mut res = Response::new(StatusCode::NotFound);
+        res.set_body(format!("no {}", req.url().path()));
+        Ok(res)
+    });
+
+    let mut res = app.get("/missing").await?;
+    assert_eq!(res.status(), StatusCode::NotFound);
+    assert_eq!(res.body_string().await?, "no /missing");
+    Ok(())
+}
+
+#[async_std::test]
+async fn custom_method_not_allowed() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").post(|_| async { Ok("posted") });
+    app.method_not_allowed(|_| async {
+        Ok(Response::builder(StatusCode::MethodNotAllowed).body("nope"))
+    });
+
+    let mut res = app.get("/").await?;
+    assert_eq!(res.status(), StatusCode::MethodNotAllowed);
+    assert_eq!(res[ALLOW], "OPTIONS, POST");
+    assert_eq!(res.body_string().await?, "nope");
+    Ok(())
+}
+
+#[async_std::test]
+async fn endpoint_allow_header_is_kept() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").post(|_| async { Ok("posted") });
+    app.method_not_allowed(|_| async {
+        Ok(Response::builder(StatusCode::MethodNotAllowed).header(ALLOW, "POST, PATCH"))
+    });
+
+    let res = app.get("/").await?;
+    assert_eq!(res[ALLOW], "POST, PATCH");
+    Ok(())
+}
+
+#[async_std::test]
+async fn automatic_options() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/users").get(|_| async { Ok("list") });
+    app.at("/users").post(|_| async { Ok("create") });
+
+    let mut res = app.options("/users").await?;
+    assert_eq!(res.status(), StatusCode::NoContent);
+    assert_eq!(res[ALLOW], "GET, HEAD, OPTIONS, POST");
+    assert_eq!(res.body_string().await?, "");
+
+    let res = app.options("/missing").await?;
+    assert_eq!(res.status(), StatusCode::NotFound);
+    assert!(res.header(ALLOW).is_none());
+    Ok(())
+}
+
+#[async_std::test]
+async fn explicit_options_wins() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { Ok("get") });
+    app.at("/").options(|_| async { Ok("custom options") });
+
+    let mut res = app.options("/").await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    assert!(res.header(ALLOW).is_none());
+    assert_eq!(res.body_string().await?, "custom options");
+    Ok(())
+}
+
+#[async_std::test]
+async fn nested_options() -> tide::Result<()> {
+    let mut inner = tide::new();
+    inner.at("/items").put(|_| async { Ok("put") });
+    let mut app = tide::new();
+    app.at("/api").nest(inner);
+
+    let res = app.options("/api/items").await?;
+    assert_eq!(res.status(), StatusCode::NoContent);
+    assert_eq!(res[ALLOW], "OPTIONS, PUT");
+    Ok(())
+}
@@ -0,0 +1,115 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use tide::Request;
+
+#[async_std::test]
+async fn dispatches_on_host_before_path() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.host("api.example.com")
+        .at("/users")
+        .get(|_| async { Ok("api users") });
+    app.host("www.example.com")
+        .get(|_| async { Ok("www root") });
+    app.at("/users").get(|_| async { Ok("default users") });
+    app.at("/").get(|_| async { Ok("default root") });
+
+    assert_eq!(
+        app.get("http://api.example.com/users")
+            .recv_string()
+            .await?,
+        "api users"
+    );
+    assert_eq!(
+        app.get("http://API.example.com:8080/users")
+            .recv_string()
+            .await?,
+        "api users"
+    );
+    assert_eq!(
+        app.get("http://www.example.com/").recv_string().await?,
+        "www root"
+    );
+    assert_eq!(
+        app.get("http://other.example.com/users")
+            .recv_string()
+            .await?,
+        "default users"
+    );
+    assert_eq!(
+        app.get("http://localhost/").recv_string().await?,
+        "default root"
+    );
+
+    // Hosts only serve their own routes.
+    assert_eq!(app.get("http://api.example.com/").await?.status(), 404);
+    assert_eq!(
+        app.post("http://api.example.com/users").await?.status(),
+        405
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn wildcard_subdomains() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.host("*.example.com")
+        .at("/posts/:id")
+        .get(|req: Request<()>| async move {
+            Ok(format!("{} {}", req.param("subdomain")?, req.param("id")?))
+        });
+    app.at("/*").get(|_| async { Ok("default") });
+
+    assert_eq!(
+        app.get("http://nori.example.com/posts/1")
+            .recv_string()
+            .await?,
+        "nori 1"
+    );
+    assert_eq!(
+        app.get("http://example.com/posts/1").recv_string().await?,
+        "default"
+    );
+    assert_eq!(
+        app.get("http://a.b.example.com/posts/1")
+            .recv_string()
+            .await?,
+        "default"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn nest_server_on_host() -> tide::Result<()> {
+    let mut api = tide::new();
+    api.at("/users/:id")
+        .name("user")
+        .get(|req: Request<()>| async move { req.url_for("user", &[("id", "2")]) });
+
+    let mut app = tide::new();
+    app.host("api.example.com").nest(api);
+    app.not_found(|_| async { Ok("custom not found") });
+
+    assert_eq!(
+        app.get("http://api.example.com/users/1")
+            .recv_string()
+            .await?,
+        "/users/2"
+    );
+    // Unmatched paths are answered by the nested server.
+    assert_eq!(
+        app.get("http://api.example.com/nothing").await?.status(),
+        404
+    );
+    assert_eq!(
+        app.get("http://example.com/users/1").recv_string().await?,
+        "custom not found"
+    );
+
+    let routes: Vec<String> = app.routes().map(|route| route.to_string()).collect();
+    assert_eq!(
+        routes,
+        vec!["* api.example.com/*", "GET api.example.com/users/:id"]
+    );
+    Ok(())
+}
@@ -0,0 +1,237 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use async_std::io::{self, Read};
+use async_std::prelude::*;
+use serde::Deserialize;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tide::{Body, Request, StatusCode};
+
+const CONTENT_TYPE: &str = "multipart/form-data; boundary=XyZ";
+
+fn form(parts: &[(&str, Option<&str>, &str)]) -> String {
+    let mut body = String::new();
+    for (name, filename, contents) in parts {
+        body.push_str("--XyZ\r\n");
+        match filename {
+            Some(filename) => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                 Content-Type: text/plain\r\n",
+                name, filename
+            )),
+            None => body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{}\"\r\n",
+                name
+            )),
+        }
+        body.push_str("\r\n");
+        body.push_str(contents);
+        body.push_str("\r\n");
+    }
+    body.push_str("--XyZ--\r\n");
+    body
+}
+
+/// A body of unknown length read a few bytes at a time.
+struct Trickle(io::Cursor<Vec<u8>>);
+
+impl Read for Trickle {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(3);
+        Pin::new(&mut self.0).poll_read(cx, &mut buf[..len])
+    }
+}
+
+fn trickle(body: String) -> Body {
+    Body::from_reader(
+        io::BufReader::new(Trickle(io::Cursor::new(body.into()))),
+        None,
+    )
+}
+
+/// Describe every part as `name:filename:type=contents`.
+async fn describe(mut req: Request<()>) -> tide::Result<String> {
+    let mut parts = req.body_multipart()?;
+    let mut lines = Vec::new();
+    while let Some(part) = parts.next().await {
+        let mut part = part?;
+        let contents = part.body_string().await?;
+        lines.push(format!(
+            "{}:{}:{}={}",
+            part.name(),
+            part.filename().unwrap_or(""),
+            part.content_type().map(|mime| mime.essence()).unwrap_or(""),
+            contents
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Count the parts, reading none of them.
+async fn count(mut req: Request<()>) -> tide::Result<String> {
+    let mut parts = req.body_multipart()?.field_limit(8);
+    let mut count = 0;
+    while let Some(part) = parts.next().await {
+        part?;
+        count += 1;
+    }
+    Ok(count.to_string())
+}
+
+#[async_std::test]
+async fn parses_parts() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").post(describe);
+
+    let body = form(&[
+        ("title", None, "Hello"),
+        ("file", Some("notes.txt"), "line one\r\nline two --XyZ"),
+        ("empty", None, ""),
+    ]);
+    let expected = "title::=Hello\n\
+                    file:notes.txt:text/plain=line one\r\nline two --XyZ\n\
+                    empty::=";
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(body.clone())
+        .recv_string()
+        .await?;
+    assert_eq!(res, expected);
+
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(trickle(format!("preamble\r\n{}epilogue", body)))
+        .recv_string()
+        .await?;
+    assert_eq!(res, expected);
+    Ok(())
+}
+
+#[async_std::test]
+async fn skips_unread_parts() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").post(count);
+
+    let body = form(&[
+        ("a", None, "1"),
+        ("b", Some("b.txt"), "22"),
+        ("c", None, ""),
+    ]);
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(body)
+        .recv_string()
+        .await?;
+    assert_eq!(res, "3");
+    Ok(())
+}
+
+#[async_std::test]
+async fn enforces_limits() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/field").post(count);
+    app.at("/total").body_limit(64).post(describe);
+
+    let body = form(&[("a", None, "123456789")]);
+    let res = app
+        .post("/field")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(body.clone())
+        .await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+
+    let body = form(&[("a", None, &"x".repeat(100))]);
+    let res = app
+        .post("/total")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(trickle(body))
+        .await?;
+    assert_eq!(res.status(), StatusCode::PayloadTooLarge);
+    Ok(())
+}
+
+#[async_std::test]
+async fn rejects_invalid_bodies() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").post(describe);
+
+    let res = app.post("/").body("a=1").await?;
+    assert_eq!(res.status(), StatusCode::UnsupportedMediaType);
+
+    let res = app
+        .post("/")
+        .header("Content-Type", "multipart/form-data")
+        .body("")
+        .await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+
+    // Truncated before the closing delimiter.
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body("--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1")
+        .await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+
+    // A part without a name.
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body("--XyZ\r\nContent-Disposition: form-data\r\n\r\n1\r\n--XyZ--")
+        .await?;
+    assert_eq!(res.status(), StatusCode::BadRequest);
+    Ok(())
+}
+
+#[async_std::test]
+async fn collects_fields() -> tide::Result<()> {
+    #[derive(Deserialize)]
+    struct Signup {
+        name: String,
+        age: u8,
+        tags: Vec<String>,
+    }
+
+    let mut app = tide::new();
+    app.at("/").post(|mut req: Request<()>| async move {
+        let signup: Signup = req.body_multipart()?.fields().await?;
+        Ok(format!(
+            "{} {} {}",
+            signup.name,
+            signup.age,
+            signup.tags.join(",")
+        ))
+    });
+
+    let body = form(&[
+        ("name", None, "Chashu & co"),
+        ("avatar", Some("cat.png"), "not a field"),
+        ("age", None, "4"),
+        ("tags[0]", None, "cat"),
+        ("tags[1]", None, "black"),
+    ]);
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(body)
+        .recv_string()
+        .await?;
+    assert_eq!(res, "Chashu & co 4 cat,black");
+
+    let body = form(&[("name", None, "Nori"), ("age", None, "old")]);
+    let res = app
+        .post("/")
+        .header("Content-Type", CONTENT_TYPE)
+        .body(body)
+        .await?;
+    assert_eq!(res.status(), StatusCode::UnprocessableEntity);
+    Ok(())
+}
@@ -0,0 +1,96 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use tide::Request;
+
+/// Respond with the generated URL, or with the error message.
+fn url_for(req: &Request<()>, name: &str, params: &[(&str, &str)]) -> String {
+    match req.url_for(name, params) {
+        Ok(url) => url,
+        Err(error) => error.to_string(),
+    }
+}
+
+#[async_std::test]
+async fn builds_urls_for_named_routes() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/").name("home").get(|_| async { Ok("home") });
+    app.at("/users/:id/posts/:post")
+        .name("post")
+        .get(|_| async { Ok("post") });
+    app.at("/files/*")
+        .name("files")
+        .get(|_| async { Ok("file") });
+    app.at("/urls").get(|req: Request<()>| async move {
+        Ok([
+            url_for(&req, "home", &[]),
+            url_for(&req, "post", &[("id", "42"), ("post", "hello world")]),
+            url_for(&req, "post", &[("id", ".."), ("post", "a.txt")]),
+            url_for(&req, "files", &[("*", "a/b c.txt")]),
+            url_for(&req, "files", &[]),
+        ]
+        .join("\n"))
+    });
+
+    assert_eq!(
+        app.get("/urls").recv_string().await?,
+        "/\n/users/42/posts/hello%20world\n/users/%2E%2E/posts/a.txt\n\
+         /files/a/b%20c.txt\n/files/"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn includes_nested_prefixes() -> tide::Result<()> {
+    let mut inner = tide::new();
+    inner
+        .at("/users/:id")
+        .name("user")
+        .get(|req: Request<()>| async move { Ok(url_for(&req, "home", &[])) });
+
+    let mut api = tide::new();
+    api.at("/v1").nest(inner);
+
+    let mut app = tide::new();
+    app.at("/").name("home").get(|_| async { Ok("home") });
+    app.at("/api").nest(api);
+    app.at("/me")
+        .get(|req: Request<()>| async move { Ok(url_for(&req, "user", &[("id", "7")])) });
+
+    assert_eq!(app.get("/me").recv_string().await?, "/api/v1/users/7");
+    // Nested servers see the names of the outermost server.
+    assert_eq!(app.get("/api/v1/users/7").recv_string().await?, "/");
+    Ok(())
+}
+
+#[async_std::test]
+async fn reports_invalid_params() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.at("/users/:id")
+        .name("user")
+        .get(|_| async { Ok("user") });
+    app.at("/errors").get(|req: Request<()>| async move {
+        Ok([
+            url_for(&req, "user", &[]),
+            url_for(&req, "user", &[("id", "1"), ("name", "nori")]),
+            url_for(&req, "nobody", &[]),
+        ]
+        .join("\n"))
+    });
+
+    assert_eq!(
+        app.get("/errors").recv_string().await?,
+        "Param \"id\" missing for route \"user\"\n\
+         Route \"user\" has no param \"name\"\n\
+         No route named \"nobody\""
+    );
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "A route named `user` is already registered")]
+fn duplicate_names_panic() {
+    let mut app = tide::new();
+    app.at("/users/:id").name("user");
+    app.at("/people/:id").name("user");
+}
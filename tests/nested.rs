@@ -62,3 +62,28 @@ async fn nested_with_different_state() -> tide::Result<()> {
     assert_eq!(outer.get("/").recv_string().await?, "Hello, world!");
     Ok(())
 }
+
+#[async_std::test]
+async fn nested_with_projected_state() -> tide::Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Outer {
+        visits: Arc<AtomicUsize>,
+    }
+
+    let mut outer = tide::with_state(Outer::default());
+    outer.at("/count").nest_with(
+        |route| {
+            route.at("/").get(|req: tide::Request<usize>| async move {
+                Ok(format!("visit {}", req.state()))
+            });
+        },
+        |state: &Outer| state.visits.fetch_add(1, Ordering::SeqCst) + 1,
+    );
+
+    assert_eq!(outer.get("/count").recv_string().await?, "visit 1");
+    assert_eq!(outer.get("/count").recv_string().await?, "visit 2");
+    Ok(())
+}
@@ -37,3 +37,78 @@ async fn hello_world_parametrized() -> Result<()> {
     assert_eq!(res.body_string().await?, "iron says hello");
     Ok(())
 }
+
+#[async_std::test]
+async fn typed_param() -> Result<()> {
+    let mut server = tide::new();
+    server.at("/users/:id").get(|req: Request<()>| async move {
+        let id: u64 = req.param_as("id")?;
+        Ok(format!("user #{}", id))
+    });
+    server
+        .at("/tags/:tag")
+        .get(|req: Request<()>| async move { req.param_as::<String>("tag") });
+
+    let req = http_types::Request::new(Method::Get, Url::parse("http://example.com/users/42")?);
+    let mut res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.body_string().await?, "user #42");
+
+    let req = http_types::Request::new(Method::Get, Url::parse("http://example.com/users/nori")?);
+    let res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.status(), 400);
+
+    let req = http_types::Request::new(
+        Method::Get,
+        Url::parse("http://example.com/tags/hello%20world+1")?,
+    );
+    let mut res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.body_string().await?, "hello world+1");
+
+    let req = http_types::Request::new(Method::Get, Url::parse("http://example.com/tags/%FF")?);
+    let res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.status(), 400);
+    Ok(())
+}
+
+#[async_std::test]
+async fn deserialize_params() -> Result<()> {
+    #[derive(serde::Deserialize)]
+    struct File {
+        user: u64,
+        wildcard: String,
+    }
+
+    let mut server = tide::new();
+    server
+        .at("/users/:user/files/*")
+        .get(|req: Request<()>| async move {
+            let File { user, wildcard } = req.params()?;
+            Ok(format!("{} owns {}", user, wildcard))
+        });
+
+    let req = http_types::Request::new(
+        Method::Get,
+        Url::parse("http://example.com/users/7/files/a%20b/c&d.txt")?,
+    );
+    let mut res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.body_string().await?, "7 owns a b/c&d.txt");
+
+    let req = http_types::Request::new(
+        Method::Get,
+        Url::parse("http://example.com/users/nori/files/a.txt")?,
+    );
+    let mut res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.status(), 400);
+    assert!(res.body_string().await?.is_empty());
+
+    server
+        .at("/dirs/:wildcard/*")
+        .get(|req: Request<()>| async move {
+            let File { wildcard, .. } = req.params()?;
+            Ok(wildcard)
+        });
+    let req = http_types::Request::new(Method::Get, Url::parse("http://example.com/dirs/a/b")?);
+    let res: http_types::Response = server.respond(req).await?;
+    assert_eq!(res.status(), 500);
+    Ok(())
+}
@@ -0,0 +1,85 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use std::future::Future;
+use std::pin::Pin;
+use tide::http::Method;
+use tide::{Next, Request};
+
+type BoxFuture<'a> = Pin<Box<dyn Future<Output = tide::Result> + Send + 'a>>;
+
+/// Strip a `/fr` locale prefix, recording the locale in an extension.
+fn strip_locale<'a>(mut req: Request<()>, next: Next<'a, ()>) -> BoxFuture<'a> {
+    let path = req.url().path().to_owned();
+    if let Some(rest) = path.strip_prefix("/fr") {
+        req.set_ext("fr");
+        let rest = if rest.is_empty() { "/" } else { rest };
+        req.url_mut().set_path(rest);
+    }
+    Box::pin(async move { Ok(next.run(req).await) })
+}
+
+/// Treat `POST ?_method=DELETE` as `DELETE`.
+fn method_override<'a>(mut req: Request<()>, next: Next<'a, ()>) -> BoxFuture<'a> {
+    if req.method() == Method::Post && req.url().query() == Some("_method=DELETE") {
+        req.set_method(Method::Delete);
+    }
+    Box::pin(async move { Ok(next.run(req).await) })
+}
+
+#[async_std::test]
+async fn rewritten_urls_are_routed() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.with_pre_routing(strip_locale);
+    app.at("/").get(|req: Request<()>| async move {
+        Ok(match req.ext::<&str>() {
+            Some(_) => "Bonjour",
+            None => "Hello",
+        })
+    });
+    app.at("/users/:id")
+        .get(|req: Request<()>| async move { Ok(req.param("id")?.to_owned()) });
+
+    assert_eq!(app.get("/").recv_string().await?, "Hello");
+    assert_eq!(app.get("/fr").recv_string().await?, "Bonjour");
+    assert_eq!(app.get("/fr/users/7").recv_string().await?, "7");
+    Ok(())
+}
+
+#[async_std::test]
+async fn method_overrides_are_routed() -> tide::Result<()> {
+    let mut app = tide::new();
+    app.with_pre_routing(method_override);
+    app.at("/posts/:id")
+        .post(|_| async { Ok("updated") })
+        .delete(|_| async { Ok("deleted") });
+
+    assert_eq!(app.post("/posts/1").recv_string().await?, "updated");
+    assert_eq!(
+        app.post("/posts/1?_method=DELETE").recv_string().await?,
+        "deleted"
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn runs_before_server_middleware() -> tide::Result<()> {
+    fn record_path<'a>(req: Request<()>, next: Next<'a, ()>) -> BoxFuture<'a> {
+        let path = req.url().path().to_owned();
+        Box::pin(async move {
+            let mut res = next.run(req).await;
+            res.insert_header("x-path", path);
+            Ok(res)
+        })
+    }
+
+    let mut app = tide::new();
+    app.with(record_path);
+    app.with_pre_routing(strip_locale);
+    app.at("/users").get(|_| async { Ok("users") });
+
+    let mut res = app.get("/fr/users").await?;
+    assert_eq!(res["x-path"], "/users");
+    assert_eq!(res.body_string().await?, "users");
+    Ok(())
+}
@@ -0,0 +1,104 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use tide::http::headers;
+use tide::StatusCode;
+
+const CONTENT: &str = "0123456789abcdefghij";
+
+fn app(dir: &tempfile::TempDir) -> tide::Result<tide::Server<()>> {
+    std::fs::write(dir.path().join("file.txt"), CONTENT)?;
+    let mut app = tide::new();
+    app.at("/static/*").serve_dir(dir.path())?;
+    app.at("/file.txt")
+        .serve_file(dir.path().join("file.txt"))?;
+    Ok(app)
+}
+
+#[async_std::test]
+async fn serves_single_ranges() -> tide::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let app = app(&dir)?;
+
+    for path in ["/static/file.txt", "/file.txt"] {
+        let mut res = app.get(path).await?;
+        assert_eq!(res.status(), StatusCode::Ok);
+        assert_eq!(res[headers::ACCEPT_RANGES], "bytes");
+        assert_eq!(res.body_string().await?, CONTENT);
+
+        let mut res = app.get(path).header("Range", "bytes=2-5").await?;
+        assert_eq!(res.status(), StatusCode::PartialContent);
+        assert_eq!(res[headers::CONTENT_RANGE], "bytes 2-5/20");
+        assert_eq!(res.body_string().await?, "2345");
+
+        let mut res = app.get(path).header("Range", "bytes=-3").await?;
+        assert_eq!(res.status(), StatusCode::PartialContent);
+        assert_eq!(res[headers::CONTENT_RANGE], "bytes 17-19/20");
+        assert_eq!(res.body_string().await?, "hij");
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn serves_multiple_ranges() -> tide::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let app = app(&dir)?;
+
+    let mut res = app
+        .get("/static/file.txt")
+        .header("Range", "bytes=0-1, 18-")
+        .await?;
+    assert_eq!(res.status(), StatusCode::PartialContent);
+    let content_type = res[headers::CONTENT_TYPE].as_str().to_owned();
+    let boundary = content_type
+        .strip_prefix("multipart/byteranges;boundary=")
+        .unwrap();
+    let body = res.body_string().await?;
+    assert!(body.contains("Content-Range: bytes 0-1/20\r\n\r\n01\r\n"));
+    assert!(body.contains("Content-Range: bytes 18-19/20\r\n\r\nij\r\n"));
+    assert!(body.ends_with(&format!("\r\n--{}--\r\n", boundary)));
+    Ok(())
+}
+
+#[async_std::test]
+async fn rejects_unsatisfiable_ranges() -> tide::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let app = app(&dir)?;
+
+    let res = app.get("/file.txt").header("Range", "bytes=20-").await?;
+    assert_eq!(res.status(), StatusCode::RequestedRangeNotSatisfiable);
+    assert_eq!(res[headers::CONTENT_RANGE], "bytes */20");
+
+    // Malformed ranges are ignored.
+    let res = app.get("/file.txt").header("Range", "bytes=5-2").await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    Ok(())
+}
+
+#[async_std::test]
+async fn checks_if_range() -> tide::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let app = app(&dir)?;
+
+    let res = app.get("/static/file.txt").await?;
+    let etag = res[headers::ETAG].as_str().to_owned();
+    let last_modified = res[headers::LAST_MODIFIED].as_str().to_owned();
+
+    for if_range in [&*etag, &*last_modified] {
+        let res = app
+            .get("/static/file.txt")
+            .header("Range", "bytes=0-0")
+            .header("If-Range", if_range)
+            .await?;
+        assert_eq!(res.status(), StatusCode::PartialContent, "{}", if_range);
+    }
+
+    let mut res = app
+        .get("/static/file.txt")
+        .header("Range", "bytes=0-0")
+        .header("If-Range", "\"stale\"")
+        .await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    assert_eq!(res.body_string().await?, CONTENT);
+    Ok(())
+}
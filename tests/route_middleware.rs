@@ -128,3 +128,36 @@ async fn subroute_not_nested() -> tide::Result<()> {
     assert_eq!(res["x-child"], "child");
     Ok(())
 }
+
+#[async_std::test]
+async fn grouped_middleware() -> tide::Result<()> {
+    let mut app = tide::new();
+    let mut admin = app.at("/admin");
+    admin.with(TestMiddleware::with_header_name("X-Admin", "admin"));
+    admin.get(echo_path);
+    admin.group(|group| {
+        group.with(TestMiddleware::with_header_name("X-Group", "group"));
+        group.at("/users").get(echo_path);
+        group.at("/settings").get(echo_path);
+    });
+    admin.at("/help").get(echo_path);
+
+    for path in &["/admin/users", "/admin/settings"] {
+        let res = app.get(path).await?;
+        assert_eq!(res["X-Admin"], "admin");
+        assert_eq!(res["X-Group"], "group");
+    }
+    for path in &["/admin", "/admin/help"] {
+        let res = app.get(path).await?;
+        assert_eq!(res["X-Admin"], "admin");
+        assert!(res.header("X-Group").is_none());
+    }
+
+    let grouped: Vec<_> = app
+        .routes()
+        .filter(|route| route.middleware().len() == 2)
+        .map(|route| route.path().to_owned())
+        .collect();
+    assert_eq!(grouped, vec!["/admin/users", "/admin/settings"]);
+    Ok(())
+}
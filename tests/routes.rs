@@ -0,0 +1,93 @@
+use tide::http::Method;
+use tide::{Middleware, Next, Request, RouteInfo};
+
+struct Named(&'static str);
+
+#[async_trait::async_trait]
+impl<State: Clone + Send + Sync + 'static> Middleware<State> for Named {
+    async fn handle(&self, req: Request<State>, next: Next<'_, State>) -> tide::Result {
+        Ok(next.run(req).await)
+    }
+
+    fn name(&self) -> &str {
+        self.0
+    }
+}
+
+fn describe(routes: Vec<&RouteInfo>) -> Vec<String> {
+    routes.iter().map(|route| route.to_string()).collect()
+}
+
+/// Middleware names, skipping the cookies middleware every server adds when
+/// the `cookies` feature is enabled.
+fn named(route: &RouteInfo) -> Vec<&str> {
+    route
+        .middleware()
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !name.contains("Cookies"))
+        .collect()
+}
+
+#[test]
+fn lists_routes_in_registration_order() {
+    let mut app = tide::new();
+    app.at("/").get(|_| async { Ok("root") });
+    app.at("/users")
+        .get(|_| async { Ok("list") })
+        .post(|_| async { Ok("create") });
+    app.at("/users/:id")
+        .with(Named("auth"))
+        .delete(|_| async { Ok("deleted") });
+    app.at("/files/*").all(|_| async { Ok("files") });
+
+    let routes: Vec<_> = app.routes().collect();
+    assert_eq!(
+        describe(routes.clone()),
+        vec![
+            "GET /",
+            "GET /users",
+            "POST /users",
+            "DELETE /users/:id",
+            "* /files/*"
+        ]
+    );
+    assert_eq!(routes[3].method(), Some(Method::Delete));
+    assert_eq!(routes[3].middleware(), ["auth"]);
+    assert!(routes[0].middleware().is_empty());
+    assert!(routes[4].method().is_none());
+    assert!(routes.iter().all(|route| !route.is_nested()));
+}
+
+#[test]
+fn recurses_into_nested_servers() {
+    let mut admin = tide::new();
+    admin.with(Named("admin-server"));
+    admin.at("/").get(|_| async { Ok("dashboard") });
+    admin
+        .at("/users/:id")
+        .with(Named("audit"))
+        .put(|_| async { Ok("saved") });
+
+    let mut api = tide::new();
+    api.at("/admin").with(Named("auth")).nest(admin);
+
+    let mut app = tide::new();
+    app.at("/api").nest(api);
+
+    let routes: Vec<_> = app.routes().collect();
+    assert_eq!(
+        describe(routes.clone()),
+        vec![
+            "* /api/*",
+            "* /api/admin/*",
+            "GET /api/admin",
+            "PUT /api/admin/users/:id"
+        ]
+    );
+    assert!(routes[0].is_nested());
+    assert!(routes[1].is_nested());
+    assert!(!routes[2].is_nested());
+    assert_eq!(named(routes[1]), ["auth"]);
+    assert_eq!(named(routes[3]), ["auth", "admin-server", "audit"]);
+}
@@ -1,4 +1,6 @@
-use tide::{http, Result, Server};
+use tide::fs::ServeDirOptions;
+use tide::http::{headers, mime};
+use tide::{http, Result, Server, StatusCode};
 
 use std::fs::{self, File};
 use std::io::Write;
@@ -57,3 +59,292 @@ async fn static_endpoint_mixin() {
     assert_eq!(res.status(), 200);
     assert_eq!(res.body_string().await.unwrap().as_str(), "api");
 }
+
+#[async_std::test]
+async fn serves_under_param_prefixes() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let site = tempdir.path().join("site");
+    fs::create_dir_all(site.join("docs")).unwrap();
+    fs::write(site.join("index.html"), "<h1>Home</h1>").unwrap();
+    fs::write(site.join("docs/index.html"), "<h1>Docs</h1>").unwrap();
+
+    let mut app = Server::new();
+    app.at("/:user/site/*").serve_dir(site).unwrap();
+
+    for (path, body) in [("site/", "<h1>Home</h1>"), ("site/docs/", "<h1>Docs</h1>")] {
+        let url = format!("http://localhost/alice/{}", path);
+        let req = http_types::Request::get(http_types::Url::parse(&url).unwrap());
+        let mut res: http::Response = app.respond(req).await.unwrap();
+        assert_eq!(res.status(), 200, "{}", path);
+        assert_eq!(res.body_string().await.unwrap(), body, "{}", path);
+    }
+}
+
+#[test]
+fn accepts_paths_of_any_type() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let dir = tempdir.path();
+    let mut app = Server::new();
+    app.at("/os-str/*").serve_dir(dir.as_os_str()).unwrap();
+    app.at("/os-string/*")
+        .serve_dir(dir.as_os_str().to_owned())
+        .unwrap();
+    app.at("/cow/*")
+        .serve_dir(std::borrow::Cow::Borrowed(dir))
+        .unwrap();
+    assert!(app.at("/missing/*").serve_dir(dir.join("missing")).is_err());
+}
+
+fn site(tempdir: &tempfile::TempDir, options: ServeDirOptions) -> Result<Server<()>> {
+    let site = tempdir.path().join("site");
+    fs::create_dir_all(site.join("docs/empty"))?;
+    fs::write(site.join("index.html"), "<h1>Home</h1>")?;
+    fs::write(site.join("docs/index.htm"), "<h1>Docs</h1>")?;
+    fs::write(site.join("docs/style.css"), "h1 {}")?;
+    fs::write(site.join("docs/app.wasm"), [0, 97, 115, 109])?;
+    fs::write(site.join("docs/data.bin"), [1, 2, 3])?;
+
+    let mut app = Server::new();
+    app.at("/site/*").serve_dir_with(site, options)?;
+    Ok(app)
+}
+
+fn site_request(path: &str) -> http_types::Request {
+    http_types::Request::get(
+        http_types::Url::parse(&format!("http://localhost/site/{}", path)).unwrap(),
+    )
+}
+
+#[async_std::test]
+async fn infers_mime_types_from_extensions() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let options = ServeDirOptions::new().mime_type("bin", mime::PLAIN);
+    let app = site(&tempdir, options).unwrap();
+
+    for (path, mime) in [
+        ("index.html", mime::HTML),
+        ("docs/style.css", mime::CSS),
+        ("docs/app.wasm", mime::WASM),
+        ("docs/data.bin", mime::PLAIN),
+    ] {
+        let res: http::Response = app.respond(site_request(path)).await.unwrap();
+        assert_eq!(res.status(), 200, "{}", path);
+        assert_eq!(res.content_type(), Some(mime), "{}", path);
+    }
+}
+
+#[async_std::test]
+async fn serves_index_files() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let app = site(&tempdir, ServeDirOptions::new()).unwrap();
+
+    let mut res: http::Response = app.respond(site_request("")).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.content_type(), Some(mime::HTML));
+    assert_eq!(res.body_string().await.unwrap(), "<h1>Home</h1>");
+
+    // `docs/` has no `index.html`.
+    let res: http::Response = app.respond(site_request("docs/")).await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    let options = ServeDirOptions::new().index_files(["index.html", "index.htm"]);
+    let tempdir = tempfile::tempdir().unwrap();
+    let app = site(&tempdir, options).unwrap();
+    let mut res: http::Response = app.respond(site_request("docs/")).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body_string().await.unwrap(), "<h1>Docs</h1>");
+
+    // A file is not a directory.
+    let res: http::Response = app.respond(site_request("index.html/")).await.unwrap();
+    assert_eq!(res.status(), 404);
+}
+
+#[async_std::test]
+async fn redirects_directories_to_trailing_slash() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let app = site(&tempdir, ServeDirOptions::new()).unwrap();
+
+    let res: http::Response = app.respond(site_request("docs")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::PermanentRedirect);
+    assert_eq!(res[headers::LOCATION], "docs/");
+
+    let res: http::Response = app
+        .respond(site_request("docs/empty?page=2"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PermanentRedirect);
+    assert_eq!(res[headers::LOCATION], "empty/?page=2");
+
+    let req = http_types::Request::get(http_types::Url::parse("http://localhost/site").unwrap());
+    let res: http::Response = app.respond(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::PermanentRedirect);
+    assert_eq!(res[headers::LOCATION], "site/");
+}
+
+#[async_std::test]
+async fn lists_directories() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let site = tempdir.path().join("site");
+    fs::create_dir_all(site.join("docs/empty")).unwrap();
+    fs::write(site.join("docs/big file.txt"), "0123456789").unwrap();
+    fs::write(site.join("docs/small.txt"), "0").unwrap();
+    fs::write(site.join("docs/.secret"), "hidden").unwrap();
+
+    // Listings are opt-in.
+    let app = site_app(&site, ServeDirOptions::new());
+    let res: http::Response = app.respond(site_request("docs/")).await.unwrap();
+    assert_eq!(res.status(), 404);
+
+    let app = site_app(&site, ServeDirOptions::new().listing(true));
+    let mut res: http::Response = app.respond(site_request("docs/")).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.content_type(), Some(mime::HTML));
+    let html = res.body_string().await.unwrap();
+    assert!(html.contains("<a href=\"big%20file.txt\">big file.txt</a>"));
+    assert!(html.contains("<a href=\"empty/\">empty/</a>"));
+    assert!(!html.contains(".secret"));
+    assert!(html.find("empty/").unwrap() < html.find("big file.txt").unwrap());
+    assert!(html.find("big file.txt").unwrap() < html.find("small.txt").unwrap());
+
+    // Listed links can be followed.
+    let mut res: http::Response = app
+        .respond(site_request("docs/big%20file.txt"))
+        .await
+        .unwrap();
+    assert_eq!(res.body_string().await.unwrap(), "0123456789");
+
+    let mut req = site_request("docs/?sort=size&order=asc");
+    req.insert_header("Accept", "text/html;q=0.5, application/json");
+    let mut res: http::Response = app.respond(req).await.unwrap();
+    assert_eq!(res.content_type(), Some(mime::JSON));
+    assert_eq!(res[headers::VARY], "Accept");
+    let entries: serde_json::Value = res.body_json().await.unwrap();
+    let names: Vec<_> = entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["empty", "small.txt", "big file.txt"]);
+    assert_eq!(entries[0]["type"], "directory");
+    assert_eq!(entries[2]["type"], "file");
+    assert_eq!(entries[2]["size"], 10);
+    assert!(entries[2]["modified"].is_u64());
+
+    let app = site_app(&site, ServeDirOptions::new().listing(true).hidden(true));
+    let mut res: http::Response = app.respond(site_request("docs/")).await.unwrap();
+    assert!(res.body_string().await.unwrap().contains(".secret"));
+}
+
+#[async_std::test]
+async fn hides_dot_paths() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let site = tempdir.path().join("site");
+    fs::create_dir_all(site.join(".git")).unwrap();
+    fs::write(site.join(".git/config"), "secret").unwrap();
+    fs::write(site.join(".env"), "secret").unwrap();
+
+    let app = site_app(&site, ServeDirOptions::new().listing(true));
+    for path in [".git/", ".git/config", ".env"] {
+        let res: http::Response = app.respond(site_request(path)).await.unwrap();
+        assert_eq!(res.status(), 404, "{}", path);
+    }
+
+    let app = site_app(&site, ServeDirOptions::new().listing(true).hidden(true));
+    for path in [".git/", ".git/config", ".env"] {
+        let res: http::Response = app.respond(site_request(path)).await.unwrap();
+        assert_eq!(res.status(), 200, "{}", path);
+    }
+}
+
+fn site_app(site: &std::path::Path, options: ServeDirOptions) -> Server<()> {
+    let mut app = Server::new();
+    app.at("/site/*").serve_dir_with(site, options).unwrap();
+    app
+}
+
+#[async_std::test]
+async fn serves_precompressed_files() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let site = tempdir.path().join("site");
+    fs::create_dir_all(&site).unwrap();
+    fs::write(site.join("app.js"), "plain").unwrap();
+    fs::write(site.join("app.js.br"), "brotli").unwrap();
+    fs::write(site.join("app.js.gz"), "gzip").unwrap();
+    fs::write(site.join("style.css"), "plain").unwrap();
+    fs::write(site.join("style.css.gz"), "gzip").unwrap();
+
+    let app = site_app(&site, ServeDirOptions::new().precompressed(true));
+    for (accept_encoding, path, body, encoding) in [
+        ("gzip, deflate, br", "app.js", "brotli", Some("br")),
+        ("gzip, br;q=0.5", "app.js", "gzip", Some("gzip")),
+        ("*", "app.js", "brotli", Some("br")),
+        ("identity", "app.js", "plain", None),
+        ("br", "style.css", "plain", None),
+        ("br, gzip", "style.css", "gzip", Some("gzip")),
+    ] {
+        let mut req = site_request(path);
+        req.insert_header("Accept-Encoding", accept_encoding);
+        let mut res: http::Response = app.respond(req).await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(
+            res.header(headers::CONTENT_ENCODING).map(|v| v.as_str()),
+            encoding,
+            "{}",
+            accept_encoding
+        );
+        assert_eq!(res[headers::VARY], "Accept-Encoding");
+        let mime = if path == "app.js" {
+            mime::JAVASCRIPT
+        } else {
+            mime::CSS
+        };
+        assert_eq!(res.content_type(), Some(mime));
+        assert_eq!(
+            res.body_string().await.unwrap(),
+            body,
+            "{}",
+            accept_encoding
+        );
+    }
+
+    // Precompressed files are opt-in.
+    let app = site_app(&site, ServeDirOptions::new());
+    let mut req = site_request("app.js");
+    req.insert_header("Accept-Encoding", "br");
+    let mut res: http::Response = app.respond(req).await.unwrap();
+    assert!(res.header(headers::CONTENT_ENCODING).is_none());
+    assert_eq!(res.body_string().await.unwrap(), "plain");
+}
+
+#[async_std::test]
+async fn falls_back_for_navigation() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let site = tempdir.path().join("site");
+    fs::create_dir_all(site.join("assets")).unwrap();
+    fs::write(site.join("index.html"), "<h1>App</h1>").unwrap();
+    fs::write(site.join("assets/app.js"), "app()").unwrap();
+
+    let app = site_app(&site, ServeDirOptions::new().fallback("index.html"));
+    let navigate = |path: &str| {
+        let mut req = site_request(path);
+        req.insert_header("Accept", "text/html,application/xhtml+xml,*/*;q=0.8");
+        req
+    };
+
+    let mut res: http::Response = app.respond(navigate("settings/profile")).await.unwrap();
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.content_type(), Some(mime::HTML));
+    assert_eq!(res[headers::VARY], "Accept");
+    assert_eq!(res.body_string().await.unwrap(), "<h1>App</h1>");
+
+    // Existing files are served as usual.
+    let mut res: http::Response = app.respond(navigate("assets/app.js")).await.unwrap();
+    assert_eq!(res.body_string().await.unwrap(), "app()");
+
+    // Missing assets and requests not from navigation still fail.
+    let res: http::Response = app.respond(navigate("assets/missing.js")).await.unwrap();
+    assert_eq!(res.status(), 404);
+    let res: http::Response = app.respond(site_request("settings")).await.unwrap();
+    assert_eq!(res.status(), 404);
+}
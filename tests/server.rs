@@ -99,3 +99,38 @@ fn json() -> tide::Result<()> {
         server.race(client).await
     })
 }
+
+#[test]
+fn graceful_shutdown() -> tide::Result<()> {
+    task::block_on(async {
+        let port_a = test_utils::find_port().await;
+        let port_b = test_utils::find_port().await;
+
+        let mut app = tide::new();
+        app.at("/").get(|_| async {
+            task::sleep(Duration::from_millis(300)).await;
+            Ok("drained")
+        });
+        let shutdown = app.shutdown_handle();
+
+        let mut listener = tide::listener::ConcurrentListener::new();
+        listener.add(("localhost", port_a))?;
+        listener.add(("localhost", port_b))?;
+        let server = task::spawn(app.listen(listener));
+
+        task::sleep(Duration::from_millis(100)).await;
+        let request = task::spawn(surf::get(format!("http://localhost:{}", port_a)).recv_string());
+
+        task::sleep(Duration::from_millis(100)).await;
+        shutdown.trigger(Duration::from_secs(5));
+
+        let string = request.await?;
+        assert_eq!(string, "drained");
+        server.timeout(Duration::from_secs(1)).await??;
+        assert!(surf::get(format!("http://localhost:{}", port_b))
+            .recv_string()
+            .await
+            .is_err());
+        Ok(())
+    })
+}
@@ -0,0 +1,166 @@
+mod test_utils;
+use test_utils::ServerTestingExt;
+
+use tide::conditional::ConditionalMiddleware;
+use tide::fs::{MemoryFs, ServeDirOptions, StaticFs, TarFs};
+use tide::http::{headers, mime};
+use tide::StatusCode;
+
+/// Build a ustar archive of regular files.
+fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    for (name, contents) in files {
+        let mut header = vec![0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..108].copy_from_slice(b"0000644\0");
+        header[124..136].copy_from_slice(format!("{:011o}\0", contents.len()).as_bytes());
+        header[136..148].copy_from_slice(b"14000000000\0");
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[148..156].copy_from_slice(b"        ");
+        let sum: u32 = header.iter().map(|byte| u32::from(*byte)).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(contents);
+        archive.resize(archive.len().div_ceil(512) * 512, 0);
+    }
+    archive.resize(archive.len() + 1024, 0);
+    archive
+}
+
+const FILES: &[(&str, &[u8])] = &[
+    ("index.html", b"<h1>Home</h1>"),
+    ("js/app.js", b"console.log(\"app\");"),
+    ("js/.env", b"SECRET=1"),
+];
+
+fn app(fs: impl StaticFs) -> tide::Result<tide::Server<()>> {
+    let mut app = tide::new();
+    app.with(ConditionalMiddleware::new().max_size(0));
+    app.at("/assets/*")
+        .serve_dir_with(fs, ServeDirOptions::new().listing(true))?;
+    Ok(app)
+}
+
+fn apps() -> tide::Result<Vec<tide::Server<()>>> {
+    let memory: MemoryFs = FILES.iter().copied().collect();
+    let tar = TarFs::new(tar(FILES))?;
+    Ok(vec![app(memory)?, app(tar)?])
+}
+
+#[async_std::test]
+async fn serves_files() -> tide::Result<()> {
+    for app in apps()? {
+        let mut res = app.get("/assets/js/app.js").await?;
+        assert_eq!(res.status(), StatusCode::Ok);
+        assert_eq!(res.content_type(), Some(mime::JAVASCRIPT));
+        assert_eq!(res[headers::ACCEPT_RANGES], "bytes");
+        assert_eq!(res.body_string().await?, "console.log(\"app\");");
+
+        let mut res = app.get("/assets/").await?;
+        assert_eq!(res.content_type(), Some(mime::HTML));
+        assert_eq!(res.body_string().await?, "<h1>Home</h1>");
+
+        let res = app.get("/assets/js").await?;
+        assert_eq!(res.status(), StatusCode::PermanentRedirect);
+
+        let mut res = app.get("/assets/js/").await?;
+        let listing = res.body_string().await?;
+        assert!(listing.contains("app.js"));
+        assert!(!listing.contains(".env"));
+
+        let res = app.get("/assets/missing.js").await?;
+        assert_eq!(res.status(), StatusCode::NotFound);
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn serves_ranges_and_validators() -> tide::Result<()> {
+    for app in apps()? {
+        let mut res = app
+            .get("/assets/index.html")
+            .header("Range", "bytes=4-7")
+            .await?;
+        assert_eq!(res.status(), StatusCode::PartialContent);
+        assert_eq!(res[headers::CONTENT_RANGE], "bytes 4-7/13");
+        assert_eq!(res.body_string().await?, "Home");
+
+        let res = app.get("/assets/index.html").await?;
+        let etag = res[headers::ETAG].as_str().to_owned();
+        assert!(res.header(headers::LAST_MODIFIED).is_some());
+        let res = app
+            .get("/assets/index.html")
+            .header("If-None-Match", &*etag)
+            .await?;
+        assert_eq!(res.status(), StatusCode::NotModified);
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn serves_fingerprinted_assets() -> tide::Result<()> {
+    let fs: MemoryFs = FILES.iter().copied().collect();
+    let mut inner = tide::new();
+    inner
+        .at("/static/*")
+        .serve_dir_with(fs, ServeDirOptions::new().fingerprint(true))?;
+    inner
+        .at("/url")
+        .get(|req: tide::Request<()>| async move { req.asset_url("js/app.js") });
+    let mut app = tide::new();
+    app.at("/app").nest(inner);
+
+    let url = app.get("/app/url").recv_string().await?;
+    let hashed = url
+        .strip_prefix("/app/static/js/app.")
+        .and_then(|name| name.strip_suffix(".js"))
+        .unwrap();
+    assert_eq!(hashed.len(), 8);
+
+    let mut res = app.get(&url).await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    assert_eq!(res.content_type(), Some(mime::JAVASCRIPT));
+    assert_eq!(res[headers::CACHE_CONTROL], "max-age=31536000, immutable");
+    assert_eq!(res.body_string().await?, "console.log(\"app\");");
+
+    let res = app.get("/app/static/js/app.js").await?;
+    assert_eq!(res.status(), StatusCode::Ok);
+    assert_eq!(res[headers::CACHE_CONTROL], "max-age=60");
+    Ok(())
+}
+
+#[async_std::test]
+async fn looks_up_asset_urls() -> tide::Result<()> {
+    let fs: MemoryFs = FILES.iter().copied().collect();
+    let mut app = tide::new();
+    app.at("/assets/*")
+        .serve_dir_with(fs, ServeDirOptions::new().fingerprint(true))?;
+    app.at("/url").get(|req: tide::Request<()>| async move {
+        let path = req.url().query().unwrap_or_default();
+        Ok(req.asset_url(path).unwrap_or_else(|e| e.to_string()))
+    });
+
+    let relative = app.get("/url?js/app.js").recv_string().await?;
+    let absolute = app.get("/url?/assets/js/app.js").recv_string().await?;
+    assert!(relative.starts_with("/assets/js/app."));
+    assert_eq!(relative, absolute);
+    assert_eq!(
+        app.get("/url?js/.env").recv_string().await?,
+        "No asset \"js/.env\""
+    );
+    Ok(())
+}
+
+#[test]
+fn rejects_fingerprinting_under_params() {
+    let fs: MemoryFs = FILES.iter().copied().collect();
+    let mut app = tide::new();
+    let err = app
+        .at("/:locale/assets/*")
+        .serve_dir_with(fs, ServeDirOptions::new().fingerprint(true))
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
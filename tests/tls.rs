@@ -0,0 +1,80 @@
+mod test_utils;
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_std::net::TcpStream;
+use async_std::prelude::*;
+use async_std::task;
+use futures_rustls::rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use futures_rustls::TlsConnector;
+use http_types::{Method, Request, Url};
+use tide::prelude::*;
+
+const CERT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls_cert.pem");
+const KEY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls_key.pem");
+
+fn connector() -> TlsConnector {
+    let cert = std::fs::read(CERT).unwrap();
+    let mut roots = RootCertStore::empty();
+    for der in rustls_pemfile::certs(&mut cert.as_slice()).unwrap() {
+        roots.add(&Certificate(der)).unwrap();
+    }
+    let config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+#[test]
+fn https_listen_string() -> tide::Result<()> {
+    task::block_on(async {
+        let port = test_utils::find_port().await;
+        let mut app = tide::new();
+        app.at("/")
+            .get(|req: tide::Request<()>| async move { Ok(req.url().scheme().to_owned()) });
+
+        let listen = format!("https://localhost:{}?cert={}&key={}", port, CERT, KEY);
+        let mut listener = app.bind(listen).await?;
+        let info = listener.info();
+        assert_eq!(info.len(), 1);
+        assert!(info[0].is_encrypted());
+        assert!(info[0].connection().starts_with("https://"));
+
+        let server = task::spawn(async move {
+            listener.accept().await?;
+            Result::<(), http_types::Error>::Ok(())
+        });
+
+        let client = task::spawn(async move {
+            task::sleep(Duration::from_millis(100)).await;
+            let stream = TcpStream::connect(("localhost", port)).await?;
+            let domain = ServerName::try_from("localhost").unwrap();
+            let stream = connector().connect(domain, stream).await?;
+
+            let url = Url::parse(&format!("https://localhost:{}/", port))?;
+            let mut res = async_h1::connect(stream, Request::new(Method::Get, url)).await?;
+            assert_eq!(res.status(), 200);
+            assert_eq!(res.body_string().await?, "https");
+            Ok(())
+        });
+
+        server.race(client).await
+    })
+}
+
+#[test]
+fn missing_certificate() -> tide::Result<()> {
+    task::block_on(async {
+        let port = test_utils::find_port().await;
+        let listen = format!(
+            "tls://localhost:{}?cert=does-not-exist.pem&key={}",
+            port, KEY
+        );
+        let err = tide::new().bind(listen).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        Ok(())
+    })
+}